@@ -1,7 +1,9 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use serde::{Deserialize, Serialize};
-use chrono::{Duration, NaiveDateTime, TimeZone, Utc};
+use chrono::{Datelike, Duration, NaiveDateTime, TimeZone, Utc};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use lru::LruCache;
@@ -9,6 +11,9 @@ use std::num::NonZeroUsize;
 use thiserror::Error;
 use tracing::{info, error, warn, debug};
 use uuid::Uuid;
+use tauri::{Emitter, Manager};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::prelude::*;
 
 // ---- エラー型定義 ----
 #[derive(Error, Debug)]
@@ -25,6 +30,19 @@ pub enum AppError {
     DataParsing(String),
     #[error("Storage error: {0}")]
     Storage(String),
+    #[error("Invalid parameter: {0}")]
+    InvalidParameter(String),
+    #[error("Symbol not found: {0}")]
+    SymbolNotFound(String),
+    #[error("Incomplete response: {0}")]
+    IncompleteResponse(String),
+}
+
+impl AppError {
+    // 再試行で回復しうるエラー（現状は途中で切れた応答ボディのみ）
+    fn is_transient(&self) -> bool {
+        matches!(self, AppError::IncompleteResponse(_))
+    }
 }
 
 impl From<AppError> for String {
@@ -34,28 +52,98 @@ impl From<AppError> for String {
 }
 
 // ---- セキュアなキャッシュマネージャー ----
+// キーのハッシュでシャードに振り分け、ロック競合を減らす
+// エントリ上限はシャードごとに均等割りするため、偏ったシャードは全体の max_entries に
+// 達する前に自シャード内のLRUで追い出す（LRUは全体ではなくシャード単位の近似）
+const CACHE_SHARDS: usize = 8;
+const MIN_ENTRIES_PER_SHARD: usize = 16;
+
+#[derive(Debug)]
+struct CacheShard {
+    store: RwLock<LruCache<String, CachedData>>,
+    size_bytes: RwLock<usize>,
+}
+
 #[derive(Debug)]
 pub struct SecureCacheManager {
-    store: Arc<RwLock<LruCache<String, CachedData>>>,
+    shards: Vec<CacheShard>,
     max_size_bytes: usize,
-    current_size_bytes: Arc<RwLock<usize>>,
     session_id: String,
+    notifier: std::sync::RwLock<Option<CacheNotifier>>,
 }
 
 impl SecureCacheManager {
     pub fn new(max_entries: usize, max_size_mb: usize) -> Self {
-        let capacity = NonZeroUsize::new(max_entries).unwrap_or(NonZeroUsize::new(100).unwrap());
+        // 小さなキャッシュはシャードあたりの容量が偏らないよう分割数を抑える
+        let shard_count = (max_entries / MIN_ENTRIES_PER_SHARD).clamp(1, CACHE_SHARDS);
+        Self::with_shards(max_entries, max_size_mb, shard_count)
+    }
+
+    // エントリ上限は各シャードに均等に割り振る
+    pub fn with_shards(max_entries: usize, max_size_mb: usize, shard_count: usize) -> Self {
+        let max_entries = if max_entries == 0 { 100 } else { max_entries };
+        let shard_count = shard_count.clamp(1, max_entries);
+        let capacity = NonZeroUsize::new(max_entries.div_ceil(shard_count)).unwrap_or(NonZeroUsize::MIN);
         Self {
-            store: Arc::new(RwLock::new(LruCache::new(capacity))),
+            shards: (0..shard_count)
+                .map(|_| CacheShard { store: RwLock::new(LruCache::new(capacity)), size_bytes: RwLock::new(0) })
+                .collect(),
             max_size_bytes: max_size_mb * 1024 * 1024, // MB to bytes
-            current_size_bytes: Arc::new(RwLock::new(0)),
             session_id: Uuid::new_v4().to_string(),
+            notifier: std::sync::RwLock::new(None),
+        }
+    }
+
+    fn shard(&self, key: &str) -> &CacheShard {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        key.hash(&mut hasher);
+        &self.shards[(hasher.finish() as usize) % self.shards.len()]
+    }
+
+    // 複数シャードのロックを同時に保持しないよう1つずつ読む
+    async fn entry_count(&self) -> usize {
+        let mut count = 0;
+        for shard in &self.shards {
+            count += shard.store.read().await.len();
+        }
+        count
+    }
+
+    async fn total_size_bytes(&self) -> usize {
+        let mut total = 0;
+        for shard in &self.shards {
+            total += *shard.size_bytes.read().await;
+        }
+        total
+    }
+
+    pub fn set_notifier(&self, notifier: Option<CacheNotifier>) {
+        *self.notifier.write().unwrap_or_else(|e| e.into_inner()) = notifier;
+    }
+
+    fn has_notifier(&self) -> bool {
+        self.notifier.read().unwrap_or_else(|e| e.into_inner()).is_some()
+    }
+
+    // シャードのロックを解放してから呼ぶこと
+    async fn notify(&self, action: &str, key: Option<&str>) {
+        if !self.has_notifier() {
+            return;
+        }
+        let entry_count = self.entry_count().await;
+        if let Some(notifier) = self.notifier.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+            notifier.notify(CacheChangedEvent {
+                action: action.to_string(),
+                key: key.map(str::to_string),
+                entry_count,
+            });
         }
     }
 
     pub async fn get(&self, key: &str) -> Option<CachedData> {
         debug!("Cache GET request for key: {}", key);
-        let store = self.store.read().await;
+        let store = self.shard(key).store.read().await;
         let result = store.peek(key).cloned();
         
         if let Some(ref data) = result {
@@ -76,105 +164,118 @@ impl SecureCacheManager {
         let data_size = self.estimate_size(&data);
         
         // メモリ制限チェック
-        {
-            let current_size = *self.current_size_bytes.read().await;
-            if current_size + data_size > self.max_size_bytes {
-                warn!("Cache size limit exceeded, cleaning up");
-                self.cleanup_lru().await?;
-            }
+        if self.total_size_bytes().await + data_size > self.max_size_bytes {
+            warn!("Cache size limit exceeded, cleaning up");
+            self.cleanup_lru().await?;
         }
         
         debug!("Cache SET for key: {}, size: {} bytes", key, data_size);
         
-        {
-            let mut store = self.store.write().await;
-            if let Some(old_data) = store.put(key.clone(), data) {
-                let old_size = self.estimate_size(&old_data);
-                let mut current_size = self.current_size_bytes.write().await;
-                *current_size = current_size.saturating_sub(old_size).saturating_add(data_size);
-            } else {
-                let mut current_size = self.current_size_bytes.write().await;
-                *current_size = current_size.saturating_add(data_size);
-            }
-        }
+        let evicted_key = {
+            let shard = self.shard(&key);
+            let mut store = shard.store.write().await;
+            // push は同じキーの旧値、またはシャード容量超過で追い出したエントリを返す
+            let displaced = store.push(key.clone(), data);
+            let displaced_size = displaced.as_ref().map(|(_, old)| self.estimate_size(old)).unwrap_or(0);
+            let mut current_size = shard.size_bytes.write().await;
+            *current_size = current_size.saturating_sub(displaced_size).saturating_add(data_size);
+            displaced.map(|(old_key, _)| old_key).filter(|old_key| *old_key != key)
+        };
         
+        if let Some(old_key) = evicted_key {
+            self.notify("evict", Some(&old_key)).await;
+        }
+        self.notify("set", Some(&key)).await;
         Ok(())
     }
 
     pub async fn remove(&self, key: &str) -> bool {
         debug!("Cache REMOVE for key: {}", key);
-        let mut store = self.store.write().await;
-        if let Some(data) = store.pop(key) {
-            let data_size = self.estimate_size(&data);
-            let mut current_size = self.current_size_bytes.write().await;
-            *current_size = current_size.saturating_sub(data_size);
-            true
-        } else {
-            false
+        let shard = self.shard(key);
+        let removed = {
+            let mut store = shard.store.write().await;
+            match store.pop(key) {
+                Some(data) => {
+                    let data_size = self.estimate_size(&data);
+                    let mut current_size = shard.size_bytes.write().await;
+                    *current_size = current_size.saturating_sub(data_size);
+                    true
+                }
+                None => false,
+            }
+        };
+        if removed {
+            self.notify("remove", Some(key)).await;
         }
+        removed
     }
 
     pub async fn clear(&self) -> usize {
         info!("Clearing all cache entries");
-        let mut store = self.store.write().await;
-        let count = store.len();
-        store.clear();
-        
-        let mut current_size = self.current_size_bytes.write().await;
-        *current_size = 0;
+        let mut count = 0;
+        for shard in &self.shards {
+            let mut store = shard.store.write().await;
+            count += store.len();
+            store.clear();
+            *shard.size_bytes.write().await = 0;
+        }
         
+        self.notify("clear", None).await;
         count
     }
 
     pub async fn cleanup_expired(&self) -> usize {
         info!("Cleaning up expired cache entries");
-        let mut store = self.store.write().await;
-        let mut expired_keys = Vec::new();
-        
-        for (key, data) in store.iter() {
-            if data.is_expired() {
-                expired_keys.push(key.clone());
-            }
-        }
-        
+        let mut count = 0;
         let mut removed_size = 0;
-        for key in &expired_keys {
-            if let Some(data) = store.pop(key) {
-                removed_size += self.estimate_size(&data);
+        for shard in &self.shards {
+            let mut store = shard.store.write().await;
+            let expired_keys: Vec<String> = store.iter()
+                .filter(|(_, data)| data.is_expired())
+                .map(|(key, _)| key.clone())
+                .collect();
+            
+            let mut shard_removed = 0;
+            for key in &expired_keys {
+                if let Some(data) = store.pop(key) {
+                    shard_removed += self.estimate_size(&data);
+                }
             }
+            
+            let mut current_size = shard.size_bytes.write().await;
+            *current_size = current_size.saturating_sub(shard_removed);
+            count += expired_keys.len();
+            removed_size += shard_removed;
         }
         
-        let mut current_size = self.current_size_bytes.write().await;
-        *current_size = current_size.saturating_sub(removed_size);
-        
-        let count = expired_keys.len();
-        drop(store);
-        
+        if count > 0 {
+            self.notify("evict", None).await;
+        }
         info!("Removed {} expired entries, freed {} bytes", count, removed_size);
         count
     }
 
+    // 各シャードを現在サイズの半分まで減らす（全体でも半分以下になる）
     async fn cleanup_lru(&self) -> Result<(), AppError> {
-        let mut store = self.store.write().await;
-        let target_size = self.max_size_bytes / 2; // 半分まで減らす
-        let mut current_size = *self.current_size_bytes.read().await;
         let mut removed_size = 0;
         let mut removed_count = 0;
         
-        while current_size > target_size && !store.is_empty() {
-            if let Some((_, data)) = store.pop_lru() {
+        for shard in &self.shards {
+            let mut store = shard.store.write().await;
+            let mut size_guard = shard.size_bytes.write().await;
+            let target_size = *size_guard / 2;
+            while *size_guard > target_size {
+                let Some((_, data)) = store.pop_lru() else { break };
                 let data_size = self.estimate_size(&data);
                 removed_size += data_size;
-                current_size = current_size.saturating_sub(data_size);
+                *size_guard = size_guard.saturating_sub(data_size);
                 removed_count += 1;
-            } else {
-                break;
             }
         }
         
-        let mut size_guard = self.current_size_bytes.write().await;
-        *size_guard = current_size;
-        
+        if removed_count > 0 {
+            self.notify("evict", None).await;
+        }
         warn!("LRU cleanup: removed {} entries, freed {} bytes", removed_count, removed_size);
         Ok(())
     }
@@ -184,19 +285,22 @@ impl SecureCacheManager {
         let base_size = std::mem::size_of::<CachedData>();
         let data_size = data.data.prices.len() * 8 + // f64のサイズ
                        data.data.dates.iter().map(|s| s.len()).sum::<usize>() +
+                       data.data.opens.len() * 16 + data.data.highs.len() * 16 + // OHLCV は Option<f64>
+                       data.data.lows.len() * 16 + data.data.volumes.len() * 16 +
                        data.analysis.returns.len() * 8 +
                        data.analysis.sma5.len() * 16 + // Option<f64>
-                       data.analysis.sma20.len() * 16;
+                       data.analysis.sma20.len() * 16 +
+                       data.analysis.extra.values().map(|v| match v {
+                           IndicatorOutput::Scalar(_) => 8,
+                           IndicatorOutput::Series(s) => s.len() * 16,
+                       }).sum::<usize>();
         base_size + data_size
     }
 
     pub async fn get_stats(&self) -> CacheStats {
-        let store = self.store.read().await;
-        let current_size = *self.current_size_bytes.read().await;
-        
         CacheStats {
-            entry_count: store.len(),
-            size_bytes: current_size,
+            entry_count: self.entry_count().await,
+            size_bytes: self.total_size_bytes().await,
             max_size_bytes: self.max_size_bytes,
             session_id: self.session_id.clone(),
         }
@@ -211,6 +315,122 @@ pub struct CacheStats {
     session_id: String,
 }
 
+// ---- キャッシュ変更通知 ----
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CacheChangedEvent {
+    action: String,      // set / remove / clear / evict
+    key: Option<String>, // clear・evict では None
+    entry_count: usize,
+}
+
+pub type CacheListener = Arc<dyn Fn(CacheChangedEvent) + Send + Sync>;
+
+// debounce 期間内の連続した変更はまとめて最後の1件だけ通知する
+pub struct CacheNotifier {
+    listener: CacheListener,
+    debounce: std::time::Duration,
+    pending: Arc<std::sync::Mutex<Option<CacheChangedEvent>>>,
+}
+
+impl CacheNotifier {
+    pub fn new(listener: CacheListener, debounce: std::time::Duration) -> Self {
+        Self { listener, debounce, pending: Arc::new(std::sync::Mutex::new(None)) }
+    }
+
+    fn notify(&self, event: CacheChangedEvent) {
+        if self.debounce.is_zero() {
+            (self.listener)(event);
+            return;
+        }
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let flush_scheduled = pending.is_some();
+        *pending = Some(event);
+        if !flush_scheduled {
+            let pending = self.pending.clone();
+            let listener = self.listener.clone();
+            let delay = self.debounce;
+            tokio::spawn(async move {
+                tokio::time::sleep(delay).await;
+                let latest = pending.lock().unwrap_or_else(|e| e.into_inner()).take();
+                if let Some(event) = latest {
+                    listener(event);
+                }
+            });
+        }
+    }
+}
+
+impl std::fmt::Debug for CacheNotifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CacheNotifier").field("debounce", &self.debounce).finish()
+    }
+}
+
+// ---- テレメトリ（観測用カウンター） ----
+#[derive(Debug, Default)]
+pub struct Telemetry {
+    total_fetches: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    network_requests: AtomicU64,
+    network_latency_ms: AtomicU64,
+    network_errors: std::sync::Mutex<HashMap<String, u64>>,
+}
+
+impl Telemetry {
+    fn record_fetch(&self) {
+        self.total_fetches.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_cache_hit(&self) {
+        self.cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_cache_miss(&self) {
+        self.cache_misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_network_latency(&self, elapsed: std::time::Duration) {
+        self.network_requests.fetch_add(1, Ordering::Relaxed);
+        self.network_latency_ms.fetch_add(elapsed.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    fn record_network_error(&self, code: &str) {
+        let mut errors = self.network_errors.lock().unwrap_or_else(|e| e.into_inner());
+        *errors.entry(code.to_string()).or_insert(0) += 1;
+    }
+
+    pub fn snapshot(&self) -> TelemetrySnapshot {
+        let network_requests = self.network_requests.load(Ordering::Relaxed);
+        let latency_ms = self.network_latency_ms.load(Ordering::Relaxed);
+        TelemetrySnapshot {
+            total_fetches: self.total_fetches.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            cache_misses: self.cache_misses.load(Ordering::Relaxed),
+            network_errors: self.network_errors.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+            avg_fetch_latency_ms: if network_requests > 0 { latency_ms as f64 / network_requests as f64 } else { 0.0 },
+        }
+    }
+
+    pub fn reset(&self) {
+        self.total_fetches.store(0, Ordering::Relaxed);
+        self.cache_hits.store(0, Ordering::Relaxed);
+        self.cache_misses.store(0, Ordering::Relaxed);
+        self.network_requests.store(0, Ordering::Relaxed);
+        self.network_latency_ms.store(0, Ordering::Relaxed);
+        self.network_errors.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct TelemetrySnapshot {
+    total_fetches: u64,
+    cache_hits: u64,
+    cache_misses: u64,
+    network_errors: HashMap<String, u64>,
+    avg_fetch_latency_ms: f64,
+}
+
 // ---- Yahoo Finance v8 chart 応答（必要最小） ----
 #[derive(Deserialize)]
 struct ChartResponse { chart: Chart }
@@ -228,7 +448,13 @@ struct ResultItem {
 #[derive(Deserialize)]
 struct Indicators { quote: Vec<Quote> }
 #[derive(Deserialize)]
-struct Quote { close: Option<Vec<Option<f64>>> }
+struct Quote {
+  close: Option<Vec<Option<f64>>>,
+  open: Option<Vec<Option<f64>>>,
+  high: Option<Vec<Option<f64>>>,
+  low: Option<Vec<Option<f64>>>,
+  volume: Option<Vec<Option<f64>>>,
+}
 #[derive(Deserialize)]
 struct Meta { symbol: String, timezone: String }
 
@@ -267,16 +493,28 @@ struct CacheEntryInfo {
 }
 
 // ---- 画面へ返す系列＆解析結果 ----
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 struct SeriesPayload {
   symbol: String,
   dates: Vec<String>,
   prices: Vec<f64>,
+  // OHLCV は dates と同じ長さ。欠損バーは None
+  #[serde(default)]
+  opens: Vec<Option<f64>>,
+  #[serde(default)]
+  highs: Vec<Option<f64>>,
+  #[serde(default)]
+  lows: Vec<Option<f64>>,
+  #[serde(default)]
+  volumes: Vec<Option<f64>>,
+  // 終値が null だったため取り除いたバーの数
+  #[serde(default)]
+  dropped_nulls: usize,
   cached: Option<bool>,
   cached_at: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 struct AnalysisResult {
   mean_return_daily: f64,
   std_return_daily: f64,
@@ -284,14 +522,120 @@ struct AnalysisResult {
   sma5: Vec<Option<f64>>,
   sma20: Vec<Option<f64>>,
   returns: Vec<f64>,
+  // 既定以外の指標の出力（指標名 → 値）
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  extra: BTreeMap<String, IndicatorOutput>,
+  // 無効化された指標名。該当する専用フィールドの 0.0 / None は計算値ではない
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  disabled: Vec<String>,
+}
+
+// ---- 指標パイプライン ----
+pub struct SeriesContext<'a> {
+    pub prices: &'a [f64],
+    pub returns: Vec<f64>, // 先頭は0.0（prices と同じ長さ）
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum IndicatorOutput {
+    Scalar(f64),
+    Series(Vec<Option<f64>>),
+}
+
+pub trait Indicator: Send + Sync {
+    fn name(&self) -> &str;
+    fn compute(&self, ctx: &SeriesContext) -> IndicatorOutput;
+}
+
+struct MeanReturnIndicator;
+struct StdReturnIndicator;
+struct SharpeIndicator;
+struct SmaIndicator {
+    name: String,
+    window: usize,
+}
+
+impl SmaIndicator {
+    fn new(window: usize) -> Self {
+        Self { name: format!("sma{}", window), window }
+    }
+}
+
+// 平均・分散は先頭の0.0を含めた全期間で計算する（従来の出力との互換のため）
+fn return_moments(returns: &[f64]) -> (f64, f64) {
+    let n = returns.len() as f64;
+    let mean = returns.iter().sum::<f64>() / n;
+    let var = returns.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n.max(1.0);
+    (mean, var.sqrt())
+}
+
+impl Indicator for MeanReturnIndicator {
+    fn name(&self) -> &str { "mean_return_daily" }
+    fn compute(&self, ctx: &SeriesContext) -> IndicatorOutput {
+        IndicatorOutput::Scalar(return_moments(&ctx.returns).0)
+    }
+}
+
+impl Indicator for StdReturnIndicator {
+    fn name(&self) -> &str { "std_return_daily" }
+    fn compute(&self, ctx: &SeriesContext) -> IndicatorOutput {
+        IndicatorOutput::Scalar(return_moments(&ctx.returns).1)
+    }
+}
+
+impl Indicator for SharpeIndicator {
+    fn name(&self) -> &str { "sharpe_annual" }
+    fn compute(&self, ctx: &SeriesContext) -> IndicatorOutput {
+        let (mean, std) = return_moments(&ctx.returns);
+        IndicatorOutput::Scalar(if std > 0.0 { (mean * 252.0_f64.sqrt()) / std } else { 0.0 })
+    }
+}
+
+impl Indicator for SmaIndicator {
+    fn name(&self) -> &str { &self.name }
+    fn compute(&self, ctx: &SeriesContext) -> IndicatorOutput {
+        IndicatorOutput::Series(YahooFinanceService::calculate_sma(ctx.prices, self.window))
+    }
+}
+
+// AnalysisResult の専用フィールドに入る指標
+const DEDICATED_INDICATORS: &[&str] = &["mean_return_daily", "std_return_daily", "sharpe_annual", "sma5", "sma20"];
+
+// 既定パイプライン（専用フィールドの指標 + extra の rolling_sortino60）
+pub fn default_pipeline() -> Vec<Box<dyn Indicator>> {
+    vec![
+        Box::new(MeanReturnIndicator),
+        Box::new(StdReturnIndicator),
+        Box::new(SharpeIndicator),
+        Box::new(SmaIndicator::new(5)),
+        Box::new(SmaIndicator::new(20)),
+        Box::new(RollingSortinoIndicator::new(ROLLING_SORTINO_WINDOW, 0.0)),
+    ]
 }
 
 // ---- ビジネスロジック層 ----
 pub struct YahooFinanceService {
     client: reqwest::Client,
     cache: Arc<SecureCacheManager>,
+    telemetry: Telemetry,
+    aliases: RwLock<HashMap<String, String>>,
+    fetch_permits: tokio::sync::Semaphore,
+    chart_base_url: String,
+    // 存在しないシンボルの記憶（シンボル → 有効期限）
+    negative_cache: RwLock<HashMap<String, chrono::DateTime<Utc>>>,
+    negative_cache_minutes: AtomicI64,
+    pipeline: Vec<Box<dyn Indicator>>,
+    // 設定で無効化した指標名（パイプライン上の名前）
+    disabled_indicators: std::sync::RwLock<BTreeSet<String>>,
 }
 
+const YAHOO_CHART_BASE_URL: &str = "https://query1.finance.yahoo.com/v8/finance/chart";
+const MAX_CONCURRENT_FETCHES: usize = 4;
+const DEFAULT_NEGATIVE_CACHE_MINUTES: i64 = 5;
+const MAX_FETCH_ATTEMPTS: usize = 3;
+const FETCH_RETRY_BACKOFF_MS: u64 = 200;
+
 impl YahooFinanceService {
     pub fn new(cache: Arc<SecureCacheManager>) -> Self {
         let client = reqwest::Client::builder()
@@ -300,15 +644,72 @@ impl YahooFinanceService {
             .build()
             .unwrap();
         
-        Self { client, cache }
+        Self {
+            client,
+            cache,
+            telemetry: Telemetry::default(),
+            aliases: RwLock::new(HashMap::new()),
+            fetch_permits: tokio::sync::Semaphore::new(MAX_CONCURRENT_FETCHES),
+            chart_base_url: YAHOO_CHART_BASE_URL.to_string(),
+            negative_cache: RwLock::new(HashMap::new()),
+            negative_cache_minutes: AtomicI64::new(DEFAULT_NEGATIVE_CACHE_MINUTES),
+            pipeline: default_pipeline(),
+            disabled_indicators: std::sync::RwLock::new(BTreeSet::new()),
+        }
+    }
+
+    // 解析に使う指標を差し替える（無効化・独自指標の追加用）
+    pub fn with_pipeline(mut self, pipeline: Vec<Box<dyn Indicator>>) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    pub fn indicator_names(&self) -> Vec<String> {
+        self.pipeline.iter().map(|indicator| indicator.name().to_string()).collect()
+    }
+
+    // None はパイプラインの全指標を有効にする
+    pub async fn set_enabled_indicators(&self, enabled: Option<&[String]>) -> Result<(), AppError> {
+        let names = self.indicator_names();
+        let disabled: BTreeSet<String> = match enabled {
+            None => BTreeSet::new(),
+            Some(enabled) => {
+                if let Some(unknown) = enabled.iter().find(|name| !names.contains(name)) {
+                    return Err(AppError::InvalidParameter(format!("Unknown indicator: {} (expected one of {})", unknown, names.join(", "))));
+                }
+                names.into_iter().filter(|name| !enabled.contains(name)).collect()
+            }
+        };
+        let changed = {
+            let mut current = self.disabled_indicators.write().unwrap_or_else(|e| e.into_inner());
+            let changed = *current != disabled;
+            *current = disabled;
+            changed
+        };
+        // キャッシュ済みの解析結果は変更前の指標構成で計算されている
+        if changed {
+            self.cache.clear().await;
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    fn with_chart_base_url(mut self, base_url: &str) -> Self {
+        self.chart_base_url = base_url.trim_end_matches('/').to_string();
+        self
     }
 
     pub async fn get_financial_data(&self, symbol: &str, range: &str, interval: &str) -> Result<(SeriesPayload, AnalysisResult), AppError> {
+        // 別名（"Toyota" など）を実シンボルに解決
+        let resolved_symbol = self.resolve_symbol(symbol).await;
+        let symbol = resolved_symbol.as_str();
         let cache_key = self.generate_cache_key(symbol, range, interval);
+        self.telemetry.record_fetch();
         
         // キャッシュ確認
         if let Some(cached_data) = self.cache.get(&cache_key).await {
             info!("Cache HIT for {}", cache_key);
+            self.telemetry.record_cache_hit();
             let mut payload = (*cached_data.data).clone();
             payload.cached = Some(true);
             payload.cached_at = Some(cached_data.cached_at.to_rfc3339());
@@ -316,9 +717,48 @@ impl YahooFinanceService {
         }
 
         info!("Cache MISS for {}, fetching from Yahoo Finance", cache_key);
+        self.telemetry.record_cache_miss();
+        
+        // 直近で「存在しない」と確定したシンボルはAPIを叩かない
+        if self.is_known_missing(symbol).await {
+            debug!("Negative cache HIT for {}", symbol);
+            return Err(AppError::SymbolNotFound(symbol.to_string()));
+        }
+        
+        let fetched = self.fetch_and_cache(symbol, range, interval).await;
+        if let Err(AppError::SymbolNotFound(_)) = &fetched {
+            self.remember_missing(symbol).await;
+        }
+        let (mut final_payload, analysis_result) = fetched?;
+        final_payload.cached = Some(false);
+        final_payload.cached_at = None;
+        
+        Ok((final_payload, analysis_result))
+    }
+
+    // ネットワークから取得して解析し、キャッシュを上書きする
+    async fn fetch_and_cache(&self, symbol: &str, range: &str, interval: &str) -> Result<(SeriesPayload, AnalysisResult), AppError> {
+        let cache_key = self.generate_cache_key(symbol, range, interval);
         
-        // 新しいデータを取得
-        let series_data = self.fetch_from_yahoo(symbol, range, interval).await?;
+        // 新しいデータを取得（同時接続数を制限し、一時的なエラーは再試行）
+        let mut attempt = 1;
+        let series_data = loop {
+            let started = std::time::Instant::now();
+            let fetched = {
+                let _permit = self.fetch_permits.acquire().await
+                    .map_err(|e| AppError::YahooFinance(format!("Fetch limiter closed: {}", e)))?;
+                self.fetch_from_yahoo(symbol, range, interval).await
+            };
+            self.telemetry.record_network_latency(started.elapsed());
+            match fetched {
+                Err(e) if e.is_transient() && attempt < MAX_FETCH_ATTEMPTS => {
+                    warn!("Transient error fetching {} (attempt {}/{}): {}", symbol, attempt, MAX_FETCH_ATTEMPTS, e);
+                    tokio::time::sleep(std::time::Duration::from_millis(FETCH_RETRY_BACKOFF_MS * attempt as u64)).await;
+                    attempt += 1;
+                }
+                result => break result?,
+            }
+        };
         let analysis_result = self.analyze_financial_data(&series_data.prices)?;
         
         // キャッシュに保存
@@ -327,32 +767,52 @@ impl YahooFinanceService {
             error!("Failed to cache data: {}", e);
         }
         
-        let mut final_payload = series_data;
-        final_payload.cached = Some(false);
-        final_payload.cached_at = None;
-        
-        Ok((final_payload, analysis_result))
+        Ok((series_data, analysis_result))
     }
 
     async fn fetch_from_yahoo(&self, symbol: &str, range: &str, interval: &str) -> Result<SeriesPayload, AppError> {
         let url = format!(
-            "https://query1.finance.yahoo.com/v8/finance/chart/{}?range={}&interval={}&events=div,splits",
-            urlencoding::encode(symbol), range, interval
+            "{}/{}?range={}&interval={}&events=div,splits",
+            self.chart_base_url, urlencoding::encode(symbol), range, interval
         );
         
         debug!("Fetching from URL: {}", url);
         
-        let response = self.client
-            .get(&url)
-            .send()
-            .await?;
+        let response = match self.client.get(&url).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                let code = if e.is_timeout() { "timeout" } else if e.is_connect() { "connect" } else { "request" };
+                self.telemetry.record_network_error(code);
+                return Err(e.into());
+            }
+        };
         
         if !response.status().is_success() {
+            self.telemetry.record_network_error(response.status().as_str());
+            if response.status() == reqwest::StatusCode::NOT_FOUND {
+                return Err(AppError::SymbolNotFound(symbol.to_string()));
+            }
             return Err(AppError::YahooFinance(format!("HTTP {}: {}", response.status(), url)));
         }
         
-        let chart_response: ChartResponse = response.json().await
-            .map_err(|e| AppError::YahooFinance(format!("JSON parse error: {}", e)))?;
+        // 途中で切れたボディ（EOF）は一時的な失敗として区別する
+        let body = response.bytes().await.map_err(|e| {
+            self.telemetry.record_network_error("body");
+            AppError::IncompleteResponse(format!("Failed to read body: {}", e))
+        })?;
+        let chart_response: ChartResponse = serde_json::from_slice(&body).map_err(|e| {
+            if e.is_eof() {
+                self.telemetry.record_network_error("truncated");
+                AppError::IncompleteResponse(format!("Truncated JSON body: {}", e))
+            } else {
+                AppError::YahooFinance(format!("JSON parse error: {}", e))
+            }
+        })?;
+        
+        let error_code = chart_response.chart.error.as_ref().and_then(|e| e.get("code")).and_then(|c| c.as_str());
+        if error_code == Some("Not Found") {
+            return Err(AppError::SymbolNotFound(symbol.to_string()));
+        }
         
         let result = chart_response.chart.result
             .ok_or_else(|| AppError::YahooFinance("No result in response".to_string()))?
@@ -361,12 +821,16 @@ impl YahooFinanceService {
             .ok_or_else(|| AppError::YahooFinance("Empty result".to_string()))?;
         
         let timestamps = result.timestamp.unwrap_or_default();
-        let closes = result.indicators.quote.get(0)
-            .and_then(|q| q.close.clone())
+        let quote = result.indicators.quote.first()
+            .ok_or_else(|| AppError::YahooFinance("No quote data".to_string()))?;
+        let closes = quote.close.clone()
             .ok_or_else(|| AppError::YahooFinance("No close data".to_string()))?;
+        let field_at = |values: &Option<Vec<Option<f64>>>, i: usize| values.as_ref().and_then(|v| v.get(i).copied().flatten());
 
         let mut dates = Vec::new();
         let mut prices = Vec::new();
+        let (mut opens, mut highs, mut lows, mut volumes) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+        let mut dropped_nulls = 0;
         
         for (i, &ts) in timestamps.iter().enumerate() {
             if let Some(Some(price)) = closes.get(i) {
@@ -374,6 +838,12 @@ impl YahooFinanceService {
                     .unwrap_or_else(|| Utc.from_utc_datetime(&NaiveDateTime::from_timestamp_opt(ts, 0).unwrap()));
                 dates.push(dt.date_naive().to_string());
                 prices.push(*price);
+                opens.push(field_at(&quote.open, i));
+                highs.push(field_at(&quote.high, i));
+                lows.push(field_at(&quote.low, i));
+                volumes.push(field_at(&quote.volume, i));
+            } else {
+                dropped_nulls += 1;
             }
         }
         
@@ -385,6 +855,11 @@ impl YahooFinanceService {
             symbol: result.meta.symbol,
             dates,
             prices,
+            opens,
+            highs,
+            lows,
+            volumes,
+            dropped_nulls,
             cached: Some(false),
             cached_at: None,
         })
@@ -400,14 +875,36 @@ impl YahooFinanceService {
             returns[i] = prices[i] / prices[i-1] - 1.0;
         }
         
-        let n = prices.len() as f64;
-        let mean = returns.iter().sum::<f64>() / n;
-        let var = returns.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n.max(1.0);
-        let std = var.sqrt();
-        let sharpe = if std > 0.0 { (mean * 252.0_f64.sqrt()) / std } else { 0.0 };
+        self.analyze_with_returns(prices, returns)
+    }
 
-        let sma5 = Self::calculate_sma(prices, 5);
-        let sma20 = Self::calculate_sma(prices, 20);
+    // returns は prices と同じ長さ（先頭は0.0）。加工したリターンで指標を計算する場合にも使う
+    fn analyze_with_returns(&self, prices: &[f64], returns: Vec<f64>) -> Result<AnalysisResult, AppError> {
+        if returns.len() != prices.len() {
+            return Err(AppError::InvalidParameter("returns must align with prices".to_string()));
+        }
+        let ctx = SeriesContext { prices, returns };
+        let disabled_indicators = self.disabled_indicators.read().unwrap_or_else(|e| e.into_inner()).clone();
+        let mut outputs = Self::run_pipeline(&ctx, &self.pipeline, &disabled_indicators);
+        let disabled: Vec<String> = DEDICATED_INDICATORS.iter()
+            .filter(|name| !outputs.contains_key(**name))
+            .map(|name| name.to_string())
+            .chain(disabled_indicators.into_iter().filter(|name| !DEDICATED_INDICATORS.contains(&name.as_str())))
+            .collect();
+        // 既定の指標は専用フィールドへ。無効化されたものは 0.0 / None で埋め、disabled に名前を残す
+        let mut take_scalar = |name: &str| match outputs.remove(name) {
+            Some(IndicatorOutput::Scalar(v)) => v,
+            _ => 0.0,
+        };
+        let mean = take_scalar("mean_return_daily");
+        let std = take_scalar("std_return_daily");
+        let sharpe = take_scalar("sharpe_annual");
+        let mut take_series = |name: &str| match outputs.remove(name) {
+            Some(IndicatorOutput::Series(v)) => v,
+            _ => vec![None; prices.len()],
+        };
+        let sma5 = take_series("sma5");
+        let sma20 = take_series("sma20");
         
         Ok(AnalysisResult {
             mean_return_daily: mean,
@@ -415,10 +912,20 @@ impl YahooFinanceService {
             sharpe_annual: sharpe,
             sma5,
             sma20,
-            returns,
+            returns: ctx.returns,
+            extra: outputs,
+            disabled,
         })
     }
 
+    fn run_pipeline(ctx: &SeriesContext, pipeline: &[Box<dyn Indicator>], disabled: &BTreeSet<String>) -> BTreeMap<String, IndicatorOutput> {
+        pipeline
+            .iter()
+            .filter(|indicator| !disabled.contains(indicator.name()))
+            .map(|indicator| (indicator.name().to_string(), indicator.compute(ctx)))
+            .collect()
+    }
+
     fn calculate_sma(prices: &[f64], window: usize) -> Vec<Option<f64>> {
         let mut result = vec![None; prices.len()];
         if window == 0 { return result; }
@@ -554,6 +1061,17 @@ struct UserSettings {
   default_interval: String,
   cache_ttl_minutes: i64,
   theme: String,
+  #[serde(default = "default_negative_cache_minutes")]
+  negative_cache_minutes: i64,
+  #[serde(default)]
+  cache_events_enabled: bool,
+  // 解析に使う指標名（None なら全指標）
+  #[serde(default)]
+  enabled_indicators: Option<Vec<String>>,
+}
+
+fn default_negative_cache_minutes() -> i64 {
+  DEFAULT_NEGATIVE_CACHE_MINUTES
 }
 
 impl Default for UserSettings {
@@ -564,6 +1082,9 @@ impl Default for UserSettings {
       default_interval: "1d".to_string(),
       cache_ttl_minutes: 15,
       theme: "light".to_string(),
+      negative_cache_minutes: DEFAULT_NEGATIVE_CACHE_MINUTES,
+      cache_events_enabled: false,
+      enabled_indicators: None,
     }
   }
 }
@@ -584,12 +1105,14 @@ async fn get_user_settings(app: tauri::AppHandle) -> Result<UserSettings, String
 }
 
 #[tauri::command]
-async fn save_user_settings(settings: UserSettings, app: tauri::AppHandle) -> Result<String, String> {
+async fn save_user_settings(settings: UserSettings, app: tauri::AppHandle, service: tauri::State<'_, YahooFinanceService>) -> Result<String, String> {
   let stores = app.store_collection();
   let store = stores
     .get("settings.json")
     .ok_or("設定ストア取得失敗")?;
   
+  service.set_enabled_indicators(settings.enabled_indicators.as_deref()).await
+    .map_err(|e| e.to_string())?;
   let settings_value = serde_json::to_value(&settings)
     .map_err(|e| format!("設定シリアライズエラー: {}", e))?;
   
@@ -597,46 +1120,3378 @@ async fn save_user_settings(settings: UserSettings, app: tauri::AppHandle) -> Re
   store.save().await
     .map_err(|e| format!("設定保存エラー: {}", e))?;
   
+  service.set_negative_cache_minutes(settings.negative_cache_minutes);
+  configure_cache_events(&app, &service, settings.cache_events_enabled);
   Ok("設定を保存しました".to_string())
 }
 
-// 重複した関数を削除
+#[derive(Serialize, Debug)]
+struct IndicatorStatus {
+  name: String,
+  enabled: bool,
+}
 
+#[tauri::command]
+async fn list_indicators(service: tauri::State<'_, YahooFinanceService>) -> Result<Vec<IndicatorStatus>, String> {
+  let disabled = service.disabled_indicators.read().unwrap_or_else(|e| e.into_inner()).clone();
+  Ok(service.indicator_names().into_iter()
+    .map(|name| IndicatorStatus { enabled: !disabled.contains(&name), name })
+    .collect())
+}
 
-fn main() {
-    // ロギング初期化
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::INFO)
-        .init();
-    
-    info!("Starting Financial Dashboard Application");
-    
-    // セキュアキャッシュマネージャーを初期化 (最大100エントリ、50MB)
-    let cache_manager = Arc::new(SecureCacheManager::new(100, 50));
-    
-    // Yahoo Financeサービスを初期化
-    let yahoo_service = YahooFinanceService::new(cache_manager.clone());
-    
-    // バックグラウンドでキャッシュクリーンアップタスクを開始
-    let cleanup_cache = cache_manager.clone();
-    tokio::spawn(async move {
-        let mut interval = tokio::time::interval(std::time::Duration::from_secs(300)); // 5分間隔
-        loop {
-            interval.tick().await;
-            cleanup_cache.cleanup_expired().await;
+// ---- モンテカルロ：最大ドローダウン分布 ----
+const MAX_SIMULATION_PATHS: usize = 10_000;
+const DEFAULT_SIMULATION_SEED: u64 = 42;
+
+#[derive(Serialize, Debug)]
+struct DrawdownSimulation {
+    mean: f64,
+    p5: f64,
+    p95: f64,
+}
+
+impl YahooFinanceService {
+    // ソート済み配列の分位点（線形補間）
+    fn calculate_quantile(sorted: &[f64], p: f64) -> f64 {
+        if sorted.is_empty() { return 0.0; }
+        let pos = p.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+        let lower = pos.floor() as usize;
+        let upper = pos.ceil() as usize;
+        let weight = pos - lower as f64;
+        sorted[lower] * (1.0 - weight) + sorted[upper] * weight
+    }
+
+    // 価格（またはエクイティ）系列の最大ドローダウン（0以下の値）
+    fn calculate_max_drawdown(equity: &[f64]) -> f64 {
+        let mut peak = f64::MIN;
+        let mut max_drawdown = 0.0_f64;
+        for &value in equity {
+            peak = peak.max(value);
+            if peak > 0.0 {
+                max_drawdown = max_drawdown.min(value / peak - 1.0);
+            }
         }
-    });
-    
-    tauri::Builder::default()
-        .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_opener::init())
-        .plugin(tauri_plugin_store::Builder::default().build())
-        .manage(yahoo_service)
-        .invoke_handler(tauri::generate_handler![
-            fetch_yahoo, analyze_series, save_csv, save_yaml,
-            clear_cache, get_cache_info, remove_expired_cache,
-            get_user_settings, save_user_settings
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        max_drawdown
+    }
+
+    fn simulate_max_drawdowns(returns: &[f64], paths: usize, horizon_days: usize, seed: u64) -> Result<DrawdownSimulation, AppError> {
+        if returns.is_empty() {
+            return Err(AppError::DataParsing("Insufficient data for simulation".to_string()));
+        }
+        if paths == 0 || horizon_days == 0 {
+            return Err(AppError::InvalidParameter("paths and horizon_days must be positive".to_string()));
+        }
+        let paths = paths.min(MAX_SIMULATION_PATHS);
+
+        // 日次リターンを復元抽出してエクイティパスを生成
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut drawdowns = Vec::with_capacity(paths);
+        let mut equity = vec![0.0; horizon_days + 1];
+        for _ in 0..paths {
+            equity[0] = 1.0;
+            for day in 1..=horizon_days {
+                let r = returns[rng.gen_range(0..returns.len())];
+                equity[day] = equity[day - 1] * (1.0 + r);
+            }
+            drawdowns.push(Self::calculate_max_drawdown(&equity));
+        }
+
+        drawdowns.sort_by(|a, b| a.total_cmp(b));
+        Ok(DrawdownSimulation {
+            mean: drawdowns.iter().sum::<f64>() / drawdowns.len() as f64,
+            p5: Self::calculate_quantile(&drawdowns, 0.05),
+            p95: Self::calculate_quantile(&drawdowns, 0.95),
+        })
+    }
+}
+
+#[tauri::command]
+async fn simulate_max_drawdown(
+    symbol: String, range: String, interval: String, paths: usize, horizon_days: usize, seed: Option<u64>,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<DrawdownSimulation, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(_, analysis_result)| YahooFinanceService::simulate_max_drawdowns(
+            &analysis_result.returns[1..], paths, horizon_days, seed.unwrap_or(DEFAULT_SIMULATION_SEED),
+        ));
+    match result {
+        Ok(simulation) => Ok(simulation),
+        Err(e) => {
+            error!("simulate_max_drawdown error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- マルチファクター回帰 ----
+const MAX_FACTORS: usize = 5;
+
+// betas は呼び出し側が指定したファクター名 → 係数
+#[derive(Serialize, Debug)]
+struct FactorBetas {
+    betas: HashMap<String, f64>,
+    r_squared: f64,
 }
+
+impl YahooFinanceService {
+    // 複数系列を共通の日付に揃える（各系列の価格を同じ順序で返す）
+    fn align_series(series: &[&SeriesPayload]) -> (Vec<String>, Vec<Vec<f64>>) {
+        let Some((first, rest)) = series.split_first() else {
+            return (Vec::new(), Vec::new());
+        };
+        let lookups: Vec<HashMap<&str, f64>> = rest.iter()
+            .map(|s| s.dates.iter().map(|d| d.as_str()).zip(s.prices.iter().copied()).collect())
+            .collect();
+
+        let mut dates = Vec::new();
+        let mut aligned = vec![Vec::new(); series.len()];
+        for (date, &price) in first.dates.iter().zip(&first.prices) {
+            let others: Option<Vec<f64>> = lookups.iter().map(|m| m.get(date.as_str()).copied()).collect();
+            if let Some(others) = others {
+                dates.push(date.clone());
+                aligned[0].push(price);
+                for (column, value) in aligned[1..].iter_mut().zip(others) {
+                    column.push(value);
+                }
+            }
+        }
+        (dates, aligned)
+    }
+
+    // 先頭のプレースホルダーを含まない単純リターン
+    fn calculate_simple_returns(prices: &[f64]) -> Vec<f64> {
+        prices.windows(2).map(|w| w[1] / w[0] - 1.0).collect()
+    }
+
+    // 部分ピボット付きガウス消去
+    fn solve_linear_system(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+        let n = b.len();
+        for col in 0..n {
+            let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+            if a[pivot][col].abs() < 1e-12 { return None; }
+            a.swap(col, pivot);
+            b.swap(col, pivot);
+            let (upper, lower) = a.split_at_mut(col + 1);
+            let pivot_row = &upper[col];
+            for (offset, target) in lower.iter_mut().enumerate() {
+                let factor = target[col] / pivot_row[col];
+                for (x, p) in target[col..].iter_mut().zip(&pivot_row[col..]) {
+                    *x -= factor * p;
+                }
+                b[col + 1 + offset] -= factor * b[col];
+            }
+        }
+        let mut x = vec![0.0; n];
+        for row in (0..n).rev() {
+            let tail: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+            x[row] = (b[row] - tail) / a[row][row];
+        }
+        Some(x)
+    }
+
+    // 切片付き最小二乗法。係数（先頭が切片）とR²を返す
+    fn calculate_ols(y: &[f64], regressors: &[Vec<f64>]) -> Result<(Vec<f64>, f64), AppError> {
+        let k = regressors.len() + 1;
+        if y.len() <= k {
+            return Err(AppError::DataParsing("Insufficient data for regression".to_string()));
+        }
+        let row = |i: usize| std::iter::once(1.0).chain(regressors.iter().map(move |x| x[i]));
+
+        let mut xtx = vec![vec![0.0; k]; k];
+        let mut xty = vec![0.0; k];
+        for (i, &yi) in y.iter().enumerate() {
+            let xi: Vec<f64> = row(i).collect();
+            for a in 0..k {
+                xty[a] += xi[a] * yi;
+                for b in 0..k {
+                    xtx[a][b] += xi[a] * xi[b];
+                }
+            }
+        }
+        let coefficients = Self::solve_linear_system(xtx, xty)
+            .ok_or_else(|| AppError::DataParsing("Regressors are collinear".to_string()))?;
+
+        let mean_y = y.iter().sum::<f64>() / y.len() as f64;
+        let mut ss_res = 0.0;
+        let mut ss_tot = 0.0;
+        for (i, &yi) in y.iter().enumerate() {
+            let fitted: f64 = row(i).zip(&coefficients).map(|(x, c)| x * c).sum();
+            ss_res += (yi - fitted).powi(2);
+            ss_tot += (yi - mean_y).powi(2);
+        }
+        let r_squared = if ss_tot > 0.0 { 1.0 - ss_res / ss_tot } else { 0.0 };
+        Ok((coefficients, r_squared))
+    }
+
+    fn calculate_factor_betas(target: &SeriesPayload, factors: &[(String, SeriesPayload)]) -> Result<FactorBetas, AppError> {
+        let mut series = vec![target];
+        series.extend(factors.iter().map(|(_, f)| f));
+        let (_, aligned) = Self::align_series(&series);
+        let returns: Vec<Vec<f64>> = aligned.iter().map(|p| Self::calculate_simple_returns(p)).collect();
+
+        let (coefficients, r_squared) = Self::calculate_ols(&returns[0], &returns[1..])?;
+        let betas = factors.iter()
+            .map(|(name, _)| name.clone())
+            .zip(coefficients.into_iter().skip(1))
+            .collect();
+        Ok(FactorBetas { betas, r_squared })
+    }
+}
+
+fn validate_factors(factors: &[String]) -> Result<(), String> {
+    if factors.is_empty() || factors.len() > MAX_FACTORS {
+        return Err(format!("ファクター数は1〜{}件で指定してください", MAX_FACTORS));
+    }
+    let mut seen = std::collections::HashSet::new();
+    if let Some(duplicate) = factors.iter().find(|f| !seen.insert(f.trim().to_uppercase())) {
+        return Err(format!("ファクターが重複しています: {}", duplicate));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn multi_factor_betas(
+    symbol: String, factors: Vec<String>, range: String, interval: String,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<FactorBetas, String> {
+    validate_factors(&factors)?;
+    let result = async {
+        let (target, _) = service.get_financial_data(&symbol, &range, &interval).await?;
+        let mut factor_series: Vec<(String, SeriesPayload)> = Vec::with_capacity(factors.len());
+        for factor in &factors {
+            let (series, _) = service.get_financial_data(factor, &range, &interval).await?;
+            // 別名が同じシンボルに解決された場合も同一ファクターとして扱う
+            if let Some((other, _)) = factor_series.iter().find(|(_, s)| s.symbol == series.symbol) {
+                return Err(AppError::InvalidParameter(format!("Factors {} and {} resolve to the same symbol {}", other, factor, series.symbol)));
+            }
+            factor_series.push((factor.clone(), series));
+        }
+        YahooFinanceService::calculate_factor_betas(&target, &factor_series)
+    }.await;
+    match result {
+        Ok(betas) => Ok(betas),
+        Err(e) => {
+            error!("multi_factor_betas error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- キャッシュサイズ監査（診断用） ----
+#[derive(Serialize, Debug)]
+pub struct CacheSizeAudit {
+    estimated_bytes: usize,
+    serialized_bytes: usize,
+    ratio: f64,
+}
+
+impl SecureCacheManager {
+    // estimate_size の推定値と serde_json での実測値を比較する
+    pub async fn size_audit(&self) -> Result<CacheSizeAudit, AppError> {
+        let mut serialized_bytes = 0;
+        for shard in &self.shards {
+            let store = shard.store.read().await;
+            for (key, data) in store.iter() {
+                serialized_bytes += key.len() + serde_json::to_vec(data)?.len();
+            }
+        }
+
+        let estimated_bytes = self.total_size_bytes().await;
+        let ratio = if estimated_bytes > 0 { serialized_bytes as f64 / estimated_bytes as f64 } else { 0.0 };
+        Ok(CacheSizeAudit { estimated_bytes, serialized_bytes, ratio })
+    }
+}
+
+#[tauri::command]
+async fn cache_size_audit(service: tauri::State<'_, YahooFinanceService>) -> Result<CacheSizeAudit, String> {
+    match service.cache.size_audit().await {
+        Ok(audit) => Ok(audit),
+        Err(e) => {
+            error!("cache_size_audit error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- ローリングVaR ----
+impl YahooFinanceService {
+    // returns は analysis.returns と同じ並び（先頭は0のプレースホルダー）
+    fn calculate_rolling_var(returns: &[f64], window: usize, confidence: f64) -> Vec<Option<f64>> {
+        let mut result = vec![None; returns.len()];
+        if window == 0 { return result; }
+
+        for i in window..returns.len() {
+            let mut sample = returns[i + 1 - window..=i].to_vec();
+            sample.sort_by(|a, b| a.total_cmp(b));
+            result[i] = Some(Self::calculate_quantile(&sample, 1.0 - confidence));
+        }
+        result
+    }
+}
+
+#[tauri::command]
+async fn rolling_var(
+    symbol: String, range: String, interval: String, window: usize, confidence: f64,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<Vec<Option<f64>>, String> {
+    if window < 2 || !(confidence > 0.0 && confidence < 1.0) {
+        return Err("window は2以上、confidence は0〜1の範囲で指定してください".into());
+    }
+    match service.get_financial_data(&symbol, &range, &interval).await {
+        Ok((_, analysis_result)) => Ok(YahooFinanceService::calculate_rolling_var(&analysis_result.returns, window, confidence)),
+        Err(e) => {
+            error!("rolling_var error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- 期間別リターン寄与 ----
+impl YahooFinanceService {
+    // 各期間内の複利リターン。(1+寄与)の積が全期間リターンに一致する
+    fn calculate_return_attribution(dates: &[String], prices: &[f64], by: &str) -> Result<Vec<(String, f64)>, AppError> {
+        let key_len = match by {
+            "year" => 4,
+            "month" => 7,
+            _ => return Err(AppError::InvalidParameter(format!("Unknown attribution period: {}", by))),
+        };
+        if prices.len() < 2 || dates.len() != prices.len() {
+            return Err(AppError::DataParsing("Insufficient data for attribution".to_string()));
+        }
+
+        let mut periods: Vec<(String, f64)> = Vec::new();
+        for i in 1..prices.len() {
+            let period = dates[i].get(..key_len).unwrap_or(&dates[i]).to_string();
+            let growth = prices[i] / prices[i - 1];
+            match periods.last_mut() {
+                Some((last, factor)) if *last == period => *factor *= growth,
+                _ => periods.push((period, growth)),
+            }
+        }
+        Ok(periods.into_iter().map(|(period, factor)| (period, factor - 1.0)).collect())
+    }
+}
+
+#[tauri::command]
+async fn return_attribution(
+    symbol: String, range: String, interval: String, by: String,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<Vec<(String, f64)>, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(series_payload, _)| YahooFinanceService::calculate_return_attribution(&series_payload.dates, &series_payload.prices, &by));
+    match result {
+        Ok(contributions) => Ok(contributions),
+        Err(e) => {
+            error!("return_attribution error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- SMAクロスオーバー：バックテストとグリッドサーチ ----
+const MAX_SMA_WINDOW: usize = 250;
+
+#[derive(Serialize, Debug)]
+struct CrossoverOptimization {
+    best_fast: usize,
+    best_slow: usize,
+    sharpe: f64,
+}
+
+impl YahooFinanceService {
+    // 日次リターン列（プレースホルダーなし）の年率シャープレシオ
+    fn calculate_annualized_sharpe(returns: &[f64]) -> f64 {
+        if returns.is_empty() { return 0.0; }
+        let n = returns.len() as f64;
+        let mean = returns.iter().sum::<f64>() / n;
+        let std = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n).sqrt();
+        if std > 0.0 { mean * 252.0_f64.sqrt() / std } else { 0.0 }
+    }
+
+    // 前日のシグナル（短期SMA > 長期SMA）でロング、それ以外はノーポジション
+    fn backtest_sma_crossover(prices: &[f64], fast: usize, slow: usize) -> Vec<f64> {
+        let sma_fast = Self::calculate_sma(prices, fast);
+        let sma_slow = Self::calculate_sma(prices, slow);
+        (1..prices.len())
+            .map(|i| match (sma_fast[i - 1], sma_slow[i - 1]) {
+                (Some(f), Some(s)) if f > s => prices[i] / prices[i - 1] - 1.0,
+                _ => 0.0,
+            })
+            .collect()
+    }
+
+    fn optimize_sma_crossover(prices: &[f64], fast_range: (usize, usize), slow_range: (usize, usize)) -> Result<CrossoverOptimization, AppError> {
+        let (fast_min, fast_max) = fast_range;
+        let (slow_min, slow_max) = slow_range;
+        if fast_min == 0 || fast_min > fast_max || slow_min > slow_max || slow_max > MAX_SMA_WINDOW {
+            return Err(AppError::InvalidParameter(format!("SMA windows must satisfy 1 <= min <= max <= {}", MAX_SMA_WINDOW)));
+        }
+
+        let pairs: Vec<(usize, usize)> = (fast_min..=fast_max)
+            .flat_map(|fast| (slow_min..=slow_max).map(move |slow| (fast, slow)))
+            .filter(|&(fast, slow)| fast < slow)
+            .collect();
+
+        pairs.par_iter()
+            .map(|&(fast, slow)| (fast, slow, Self::calculate_annualized_sharpe(&Self::backtest_sma_crossover(prices, fast, slow))))
+            .max_by(|a, b| a.2.total_cmp(&b.2).then(b.0.cmp(&a.0)).then(b.1.cmp(&a.1)))
+            .map(|(best_fast, best_slow, sharpe)| CrossoverOptimization { best_fast, best_slow, sharpe })
+            .ok_or_else(|| AppError::InvalidParameter("No valid fast < slow pair in the given ranges".to_string()))
+    }
+}
+
+#[tauri::command]
+async fn optimize_sma_crossover(
+    symbol: String, range: String, interval: String, fast_range: (usize, usize), slow_range: (usize, usize),
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<CrossoverOptimization, String> {
+    // グリッド探索はCPUを占有するため、非同期ランタイムの外で実行する
+    let result = match service.get_financial_data(&symbol, &range, &interval).await {
+        Ok((series_payload, _)) => tauri::async_runtime::spawn_blocking(move || {
+            YahooFinanceService::optimize_sma_crossover(&series_payload.prices, fast_range, slow_range)
+        })
+        .await
+        .map_err(|e| AppError::DataParsing(format!("Optimization task failed: {}", e)))
+        .and_then(|optimized| optimized),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(optimization) => Ok(optimization),
+        Err(e) => {
+            error!("optimize_sma_crossover error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- 週次リターン分布 ----
+const DAILY_CAPABLE_RANGES: &[&str] = &["1mo", "3mo", "6mo", "1y", "2y", "5y", "10y", "ytd", "max"];
+
+#[derive(Serialize, Debug)]
+struct WeeklyReturnStats {
+    mean: f64,
+    std: f64,
+    skew: f64,
+    best_week: f64,
+    worst_week: f64,
+}
+
+impl YahooFinanceService {
+    // 母集団歪度（m3 / m2^1.5）
+    fn calculate_skewness(values: &[f64]) -> f64 {
+        if values.is_empty() { return 0.0; }
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let m2 = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+        let m3 = values.iter().map(|v| (v - mean).powi(3)).sum::<f64>() / n;
+        if m2 > 0.0 { m3 / m2.powf(1.5) } else { 0.0 }
+    }
+
+    // 日次系列をISO週ごとの最終終値にリサンプリングする
+    fn resample_weekly(dates: &[String], prices: &[f64]) -> (Vec<String>, Vec<f64>) {
+        let mut weekly_dates: Vec<String> = Vec::new();
+        let mut weekly_prices: Vec<f64> = Vec::new();
+        let mut current_week = None;
+        for (date, &price) in dates.iter().zip(prices) {
+            let week = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").ok().map(|d| d.iso_week());
+            if week.is_some() && week == current_week {
+                *weekly_dates.last_mut().unwrap() = date.clone();
+                *weekly_prices.last_mut().unwrap() = price;
+            } else {
+                current_week = week;
+                weekly_dates.push(date.clone());
+                weekly_prices.push(price);
+            }
+        }
+        (weekly_dates, weekly_prices)
+    }
+
+    // 週次リターン。初週は系列の初値を基準にするので、複利累積が全期間リターンに一致する
+    fn calculate_weekly_returns(dates: &[String], prices: &[f64]) -> Vec<f64> {
+        let (_, weekly_closes) = Self::resample_weekly(dates, prices);
+        let Some(&first) = prices.first() else { return Vec::new(); };
+        std::iter::once(first).chain(weekly_closes).collect::<Vec<f64>>()
+            .windows(2)
+            .map(|w| w[1] / w[0] - 1.0)
+            .collect()
+    }
+
+    fn calculate_weekly_return_stats(dates: &[String], prices: &[f64]) -> Result<WeeklyReturnStats, AppError> {
+        let weekly = Self::calculate_weekly_returns(dates, prices);
+        if weekly.len() < 2 {
+            return Err(AppError::DataParsing("Insufficient weekly data".to_string()));
+        }
+        let n = weekly.len() as f64;
+        let mean = weekly.iter().sum::<f64>() / n;
+        let std = (weekly.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n).sqrt();
+        Ok(WeeklyReturnStats {
+            mean,
+            std,
+            skew: Self::calculate_skewness(&weekly),
+            best_week: weekly.iter().copied().fold(f64::MIN, f64::max),
+            worst_week: weekly.iter().copied().fold(f64::MAX, f64::min),
+        })
+    }
+}
+
+#[tauri::command]
+async fn weekly_return_stats(symbol: String, range: String, service: tauri::State<'_, YahooFinanceService>) -> Result<WeeklyReturnStats, String> {
+    if !DAILY_CAPABLE_RANGES.contains(&range.as_str()) {
+        return Err(format!("週次集計に対応していない期間です: {}", range));
+    }
+    let result = service.get_financial_data(&symbol, &range, "1d").await
+        .and_then(|(series_payload, _)| YahooFinanceService::calculate_weekly_return_stats(&series_payload.dates, &series_payload.prices));
+    match result {
+        Ok(stats) => Ok(stats),
+        Err(e) => {
+            error!("weekly_return_stats error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- テレメトリコマンド ----
+#[tauri::command]
+async fn get_telemetry(service: tauri::State<'_, YahooFinanceService>) -> Result<TelemetrySnapshot, String> {
+    Ok(service.telemetry.snapshot())
+}
+
+#[tauri::command]
+async fn reset_telemetry(service: tauri::State<'_, YahooFinanceService>) -> Result<String, String> {
+    service.telemetry.reset();
+    info!("Telemetry counters reset");
+    Ok("テレメトリをリセットしました".to_string())
+}
+
+// ---- 出来高の先行性 ----
+impl YahooFinanceService {
+    // ピアソン相関係数
+    fn calculate_correlation(x: &[f64], y: &[f64]) -> f64 {
+        let n = x.len().min(y.len());
+        if n < 2 { return 0.0; }
+        let mean_x = x[..n].iter().sum::<f64>() / n as f64;
+        let mean_y = y[..n].iter().sum::<f64>() / n as f64;
+        let mut cov = 0.0;
+        let mut var_x = 0.0;
+        let mut var_y = 0.0;
+        for (a, b) in x[..n].iter().zip(&y[..n]) {
+            cov += (a - mean_x) * (b - mean_y);
+            var_x += (a - mean_x).powi(2);
+            var_y += (b - mean_y).powi(2);
+        }
+        if var_x > 0.0 && var_y > 0.0 { cov / (var_x * var_y).sqrt() } else { 0.0 }
+    }
+
+    // 時点tの出来高と t+lag の絶対リターンの相関
+    fn calculate_volume_lead_correlation(series: &SeriesPayload, lag: usize) -> Result<f64, AppError> {
+        let prices = &series.prices;
+        let mut volumes = Vec::new();
+        let mut moves = Vec::new();
+        for t in 0..prices.len() {
+            let target = t + lag;
+            if target == 0 || target >= prices.len() { continue; }
+            if let Some(Some(volume)) = series.volumes.get(t) {
+                volumes.push(*volume);
+                moves.push((prices[target] / prices[target - 1] - 1.0).abs());
+            }
+        }
+        if volumes.len() < 3 {
+            return Err(AppError::DataParsing("Insufficient volume data".to_string()));
+        }
+        Ok(Self::calculate_correlation(&volumes, &moves))
+    }
+}
+
+#[tauri::command]
+async fn volume_leads_price(
+    symbol: String, range: String, interval: String, lag: usize,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<f64, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(series_payload, _)| YahooFinanceService::calculate_volume_lead_correlation(&series_payload, lag));
+    match result {
+        Ok(correlation) => Ok(correlation),
+        Err(e) => {
+            error!("volume_leads_price error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- 保存：HTMLレポート（単一ファイル） ----
+impl YahooFinanceService {
+    fn escape_html(text: &str) -> String {
+        text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+    }
+
+    // 終値とSMA20の折れ線をSVG文字列として描画（フォント非依存にするためラベルなし）
+    fn render_price_chart_svg(series: &SeriesPayload, analysis: &AnalysisResult) -> Result<String, AppError> {
+        use plotters::prelude::*;
+
+        let min = series.prices.iter().copied().fold(f64::MAX, f64::min);
+        let max = series.prices.iter().copied().fold(f64::MIN, f64::max);
+        let padding = ((max - min) * 0.05).max(1e-9);
+        let chart_error = |e: String| AppError::Storage(format!("Chart rendering failed: {}", e));
+
+        let mut svg = String::new();
+        {
+            let root = SVGBackend::with_string(&mut svg, (800, 400)).into_drawing_area();
+            root.fill(&WHITE).map_err(|e| chart_error(e.to_string()))?;
+            let mut chart = ChartBuilder::on(&root)
+                .margin(10)
+                .build_cartesian_2d(0..series.prices.len(), (min - padding)..(max + padding))
+                .map_err(|e| chart_error(e.to_string()))?;
+            chart.draw_series(LineSeries::new(series.prices.iter().copied().enumerate(), &BLUE))
+                .map_err(|e| chart_error(e.to_string()))?;
+            chart.draw_series(LineSeries::new(
+                analysis.sma20.iter().enumerate().filter_map(|(i, v)| v.map(|v| (i, v))),
+                &RED,
+            )).map_err(|e| chart_error(e.to_string()))?;
+            root.present().map_err(|e| chart_error(e.to_string()))?;
+        }
+        Ok(svg)
+    }
+
+    fn render_html_report(series: &SeriesPayload, analysis: &AnalysisResult, range: &str, interval: &str) -> Result<String, AppError> {
+        let svg = Self::render_price_chart_svg(series, analysis)?;
+        let symbol = Self::escape_html(&series.symbol);
+        let period = format!(
+            "{} 〜 {}",
+            series.dates.first().map(String::as_str).unwrap_or("-"),
+            series.dates.last().map(String::as_str).unwrap_or("-"),
+        );
+        let rows = [
+            ("期間", Self::escape_html(&period)),
+            ("レンジ / 間隔", Self::escape_html(&format!("{} / {}", range, interval))),
+            ("データ点数", series.prices.len().to_string()),
+            ("最新終値", format!("{:.2}", series.prices.last().copied().unwrap_or_default())),
+            ("平均日次リターン", format!("{:.6}", analysis.mean_return_daily)),
+            ("日次標準偏差", format!("{:.6}", analysis.std_return_daily)),
+            ("年率シャープレシオ", format!("{:.4}", analysis.sharpe_annual)),
+        ];
+        let table_rows: String = rows.iter()
+            .map(|(label, value)| format!("<tr><th>{}</th><td>{}</td></tr>\n", label, value))
+            .collect();
+
+        Ok(format!(
+            r#"<!DOCTYPE html>
+<html lang="ja">
+<head>
+<meta charset="utf-8">
+<title>{symbol} レポート</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; color: #222; }}
+table {{ border-collapse: collapse; margin-bottom: 1.5em; }}
+th, td {{ border: 1px solid #ccc; padding: 4px 12px; text-align: left; }}
+footer {{ color: #666; font-size: 0.85em; }}
+</style>
+</head>
+<body>
+<h1>{symbol}</h1>
+<table>
+{table_rows}</table>
+<img alt="{symbol} 終値チャート" src="data:image/svg+xml;charset=utf-8,{chart}">
+<footer>生成日時: {generated_at} / データソース: Yahoo Finance Chart API</footer>
+</body>
+</html>
+"#,
+            symbol = symbol,
+            table_rows = table_rows,
+            chart = urlencoding::encode(&svg),
+            generated_at = Utc::now().to_rfc3339(),
+        ))
+    }
+
+    fn write_html_report(series: &SeriesPayload, analysis: &AnalysisResult, range: &str, interval: &str, output_path: &str) -> Result<(), AppError> {
+        let html = Self::render_html_report(series, analysis, range, interval)?;
+        std::fs::write(output_path, html).map_err(|e| AppError::Storage(e.to_string()))
+    }
+}
+
+#[tauri::command]
+async fn save_html_report(
+    symbol: String, range: String, interval: String, output_path: String,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<String, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(series_payload, analysis_result)| {
+            YahooFinanceService::write_html_report(&series_payload, &analysis_result, &range, &interval, &output_path)
+        });
+    match result {
+        Ok(()) => Ok(output_path),
+        Err(e) => {
+            error!("save_html_report error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- ドローダウン局面とBurkeレシオ ----
+#[derive(Debug, Clone)]
+struct DrawdownEpisode {
+    start: usize,       // 下落直前の高値のインデックス
+    trough: usize,
+    end: Option<usize>, // 回復前なら None
+    depth: f64,         // 0以下
+}
+
+impl YahooFinanceService {
+    // 各時点の高値からの下落率（0以下）
+    fn calculate_drawdown_series(prices: &[f64]) -> Vec<f64> {
+        let mut peak = f64::MIN;
+        prices.iter()
+            .map(|&p| {
+                peak = peak.max(p);
+                if peak > 0.0 { p / peak - 1.0 } else { 0.0 }
+            })
+            .collect()
+    }
+
+    // 高値更新から次の高値回復までを1局面とする
+    fn find_drawdown_episodes(prices: &[f64]) -> Vec<DrawdownEpisode> {
+        let drawdowns = Self::calculate_drawdown_series(prices);
+        let mut episodes = Vec::new();
+        let mut current: Option<DrawdownEpisode> = None;
+        for (i, &dd) in drawdowns.iter().enumerate() {
+            if dd < 0.0 {
+                let episode = current.get_or_insert(DrawdownEpisode { start: i.saturating_sub(1), trough: i, end: None, depth: 0.0 });
+                if dd < episode.depth {
+                    episode.depth = dd;
+                    episode.trough = i;
+                }
+            } else if let Some(mut episode) = current.take() {
+                episode.end = Some(i);
+                episodes.push(episode);
+            }
+        }
+        episodes.extend(current);
+        episodes
+    }
+
+    // 年率複利リターン（CAGR、252営業日換算）
+    fn calculate_annualized_return(prices: &[f64]) -> f64 {
+        if prices.len() < 2 || prices[0] <= 0.0 { return 0.0; }
+        let years = (prices.len() - 1) as f64 / 252.0;
+        (prices[prices.len() - 1] / prices[0]).powf(1.0 / years) - 1.0
+    }
+
+    // Burke = (年率リターン - 無リスク金利) / sqrt(Σ 各ドローダウン局面の深さ²)
+    // 最悪の1局面だけを見るCalmarと違い、浅い下落の積み重ねも罰する
+    fn calculate_burke_ratio(prices: &[f64], risk_free_rate: f64) -> Result<f64, AppError> {
+        if prices.len() < 2 {
+            return Err(AppError::DataParsing("Insufficient data for Burke ratio".to_string()));
+        }
+        let drawdown_norm = Self::find_drawdown_episodes(prices).iter()
+            .map(|e| e.depth.powi(2))
+            .sum::<f64>()
+            .sqrt();
+        if drawdown_norm == 0.0 {
+            return Err(AppError::DataParsing("No drawdowns in series".to_string()));
+        }
+        Ok((Self::calculate_annualized_return(prices) - risk_free_rate) / drawdown_norm)
+    }
+}
+
+#[tauri::command]
+async fn burke_ratio(
+    symbol: String, range: String, interval: String, risk_free_rate: Option<f64>,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<f64, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(series_payload, _)| YahooFinanceService::calculate_burke_ratio(&series_payload.prices, risk_free_rate.unwrap_or(0.0)));
+    match result {
+        Ok(ratio) => Ok(ratio),
+        Err(e) => {
+            error!("burke_ratio error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- シンボル別名 ----
+impl YahooFinanceService {
+    fn normalize_alias(alias: &str) -> String {
+        alias.trim().to_lowercase()
+    }
+
+    pub async fn set_alias(&self, alias: &str, symbol: &str) {
+        self.aliases.write().await.insert(Self::normalize_alias(alias), symbol.trim().to_string());
+    }
+
+    pub async fn replace_aliases(&self, aliases: HashMap<String, String>) {
+        *self.aliases.write().await = aliases;
+    }
+
+    // 別名が登録されていなければ入力をそのまま返す
+    pub async fn resolve_symbol(&self, input: &str) -> String {
+        self.aliases.read().await
+            .get(&Self::normalize_alias(input))
+            .cloned()
+            .unwrap_or_else(|| input.to_string())
+    }
+}
+
+async fn load_symbol_aliases(app: &tauri::AppHandle) -> Result<(), String> {
+  let stores = app.store_collection();
+  let store = stores
+    .get("settings.json")
+    .ok_or("設定ストア取得失敗")?;
+
+  if let Some(aliases_value) = store.get("symbol_aliases") {
+    let aliases: HashMap<String, String> = serde_json::from_value(aliases_value.clone())
+      .map_err(|e| format!("別名デシリアライズエラー: {}", e))?;
+    app.state::<YahooFinanceService>().replace_aliases(aliases).await;
+  }
+  Ok(())
+}
+
+#[tauri::command]
+async fn save_alias(alias: String, symbol: String, app: tauri::AppHandle, service: tauri::State<'_, YahooFinanceService>) -> Result<String, String> {
+  if alias.trim().is_empty() || symbol.trim().is_empty() {
+    return Err("別名とシンボルを入力してください".into());
+  }
+  service.set_alias(&alias, &symbol).await;
+
+  let stores = app.store_collection();
+  let store = stores
+    .get("settings.json")
+    .ok_or("設定ストア取得失敗")?;
+
+  let aliases_value = serde_json::to_value(&*service.aliases.read().await)
+    .map_err(|e| format!("別名シリアライズエラー: {}", e))?;
+
+  store.set("symbol_aliases", aliases_value);
+  store.save().await
+    .map_err(|e| format!("別名保存エラー: {}", e))?;
+
+  Ok(format!("別名「{}」を{}として保存しました", alias.trim(), symbol.trim()))
+}
+
+#[tauri::command]
+async fn resolve_alias(alias: String, service: tauri::State<'_, YahooFinanceService>) -> Result<Option<String>, String> {
+  Ok(service.aliases.read().await.get(&YahooFinanceService::normalize_alias(&alias)).cloned())
+}
+
+// ---- 水面下期間（Time under water） ----
+#[derive(Serialize, Debug)]
+struct TimeUnderWater {
+    longest_days: usize,
+    current_days: usize,
+}
+
+impl YahooFinanceService {
+    fn days_between(from: &str, to: &str) -> Result<usize, AppError> {
+        let parse = |d: &str| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d")
+            .map_err(|e| AppError::DataParsing(format!("Invalid date {}: {}", d, e)));
+        Ok((parse(to)? - parse(from)?).num_days().max(0) as usize)
+    }
+
+    // 高値を下回ってから回復するまでの暦日数。回復前の局面は最終日までで数える
+    fn calculate_time_under_water(dates: &[String], prices: &[f64]) -> Result<TimeUnderWater, AppError> {
+        if dates.len() != prices.len() || prices.is_empty() {
+            return Err(AppError::DataParsing("Insufficient data for time under water".to_string()));
+        }
+        let last = prices.len() - 1;
+        let mut longest_days = 0;
+        let mut current_days = 0;
+        for episode in Self::find_drawdown_episodes(prices) {
+            let days = Self::days_between(&dates[episode.start], &dates[episode.end.unwrap_or(last)])?;
+            longest_days = longest_days.max(days);
+            if episode.end.is_none() {
+                current_days = days;
+            }
+        }
+        Ok(TimeUnderWater { longest_days, current_days })
+    }
+}
+
+#[tauri::command]
+async fn time_under_water(symbol: String, range: String, interval: String, service: tauri::State<'_, YahooFinanceService>) -> Result<TimeUnderWater, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(series_payload, _)| YahooFinanceService::calculate_time_under_water(&series_payload.dates, &series_payload.prices));
+    match result {
+        Ok(underwater) => Ok(underwater),
+        Err(e) => {
+            error!("time_under_water error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- ベータ・ニュートラルなペアのウェイト ----
+#[derive(Serialize, Debug)]
+struct BetaNeutralWeights {
+    long_weight: f64,
+    short_weight: f64,
+}
+
+impl YahooFinanceService {
+    // ベンチマークに対するベータ（共分散 / ベンチマーク分散）
+    fn calculate_beta(asset_returns: &[f64], benchmark_returns: &[f64]) -> f64 {
+        let n = asset_returns.len().min(benchmark_returns.len());
+        if n < 2 { return 0.0; }
+        let mean_a = asset_returns[..n].iter().sum::<f64>() / n as f64;
+        let mean_b = benchmark_returns[..n].iter().sum::<f64>() / n as f64;
+        let cov: f64 = asset_returns[..n].iter().zip(&benchmark_returns[..n]).map(|(a, b)| (a - mean_a) * (b - mean_b)).sum();
+        let var: f64 = benchmark_returns[..n].iter().map(|b| (b - mean_b).powi(2)).sum();
+        if var > 0.0 { cov / var } else { 0.0 }
+    }
+
+    // w_long·β_long + w_short·β_short = 0 かつ |w_long| + |w_short| = 1
+    fn calculate_beta_neutral_weights(long: &SeriesPayload, short: &SeriesPayload, benchmark: &SeriesPayload) -> Result<BetaNeutralWeights, AppError> {
+        let (_, aligned) = Self::align_series(&[long, short, benchmark]);
+        if aligned[0].len() < 3 {
+            return Err(AppError::DataParsing("Insufficient overlapping data".to_string()));
+        }
+        let returns: Vec<Vec<f64>> = aligned.iter().map(|p| Self::calculate_simple_returns(p)).collect();
+        let long_beta = Self::calculate_beta(&returns[0], &returns[2]);
+        let short_beta = Self::calculate_beta(&returns[1], &returns[2]);
+
+        let gross = long_beta.abs() + short_beta.abs();
+        if gross == 0.0 {
+            return Err(AppError::DataParsing("Both legs have zero beta".to_string()));
+        }
+        Ok(BetaNeutralWeights {
+            long_weight: short_beta / gross,
+            short_weight: -long_beta / gross,
+        })
+    }
+}
+
+#[tauri::command]
+async fn beta_neutral_weights(
+    long_symbol: String, short_symbol: String, benchmark: String, range: String, interval: String,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<BetaNeutralWeights, String> {
+    let result = async {
+        let (long, _) = service.get_financial_data(&long_symbol, &range, &interval).await?;
+        let (short, _) = service.get_financial_data(&short_symbol, &range, &interval).await?;
+        let (bench, _) = service.get_financial_data(&benchmark, &range, &interval).await?;
+        YahooFinanceService::calculate_beta_neutral_weights(&long, &short, &bench)
+    }.await;
+    match result {
+        Ok(weights) => Ok(weights),
+        Err(e) => {
+            error!("beta_neutral_weights error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- 積立投資（ドルコスト平均法）バックテスト ----
+#[derive(Serialize, Debug)]
+struct DcaBacktest {
+    total_invested: f64,
+    final_value: f64,
+    irr: f64,
+}
+
+impl YahooFinanceService {
+    fn parse_iso_date(date: &str) -> Result<chrono::NaiveDate, AppError> {
+        chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|e| AppError::DataParsing(format!("Invalid date {}: {}", date, e)))
+    }
+
+    // 日付付きキャッシュフローの年率IRR（XIRR、二分法）
+    fn calculate_xirr(cash_flows: &[(chrono::NaiveDate, f64)]) -> Option<f64> {
+        let first = cash_flows.first()?.0;
+        let npv = |rate: f64| -> f64 {
+            cash_flows.iter()
+                .map(|(date, amount)| amount / (1.0 + rate).powf((*date - first).num_days() as f64 / 365.0))
+                .sum()
+        };
+        let (mut low, mut high) = (-0.99, 10.0);
+        if npv(low).signum() == npv(high).signum() { return None; }
+        for _ in 0..200 {
+            let mid = (low + high) / 2.0;
+            if npv(mid).signum() == npv(low).signum() { low = mid; } else { high = mid; }
+        }
+        Some((low + high) / 2.0)
+    }
+
+    // 週次はISO週、月次は暦月で期間を識別する
+    fn calendar_period(date: chrono::NaiveDate, frequency: &str) -> Result<(i32, u32), AppError> {
+        match frequency {
+            "weekly" => Ok((date.iso_week().year(), date.iso_week().week())),
+            "monthly" => Ok((date.year(), date.month())),
+            _ => Err(AppError::InvalidParameter(format!("Unknown frequency: {}", frequency))),
+        }
+    }
+
+    // 各週／各月の最初の取引日に一定額を買い付ける
+    fn calculate_dca_backtest(dates: &[String], prices: &[f64], contribution: f64, frequency: &str) -> Result<DcaBacktest, AppError> {
+        if !contribution.is_finite() || contribution <= 0.0 {
+            return Err(AppError::InvalidParameter("contribution must be positive".to_string()));
+        }
+        if dates.len() != prices.len() || prices.len() < 2 {
+            return Err(AppError::DataParsing("Insufficient data for DCA backtest".to_string()));
+        }
+
+        let mut shares = 0.0;
+        let mut cash_flows = Vec::new();
+        let mut last_period = None;
+        for (date, &price) in dates.iter().zip(prices) {
+            let date = Self::parse_iso_date(date)?;
+            let period = Some(Self::calendar_period(date, frequency)?);
+            if period != last_period {
+                last_period = period;
+                shares += contribution / price;
+                cash_flows.push((date, -contribution));
+            }
+        }
+
+        let final_value = shares * prices[prices.len() - 1];
+        cash_flows.push((Self::parse_iso_date(&dates[dates.len() - 1])?, final_value));
+        Ok(DcaBacktest {
+            total_invested: contribution * (cash_flows.len() - 1) as f64,
+            final_value,
+            irr: Self::calculate_xirr(&cash_flows).unwrap_or(0.0),
+        })
+    }
+}
+
+#[tauri::command]
+async fn dca_backtest(
+    symbol: String, range: String, interval: String, contribution: f64, frequency: String,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<DcaBacktest, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(series_payload, _)| YahooFinanceService::calculate_dca_backtest(&series_payload.dates, &series_payload.prices, contribution, &frequency));
+    match result {
+        Ok(backtest) => Ok(backtest),
+        Err(e) => {
+            error!("dca_backtest error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- 寄り付きギャップ分析（オーバーナイト vs 日中） ----
+#[derive(Serialize, Debug)]
+struct GapAnalysis {
+    mean_overnight: f64,
+    mean_intraday: f64,
+    gap_series: Vec<(String, f64)>,
+}
+
+impl YahooFinanceService {
+    // overnight = open[t]/close[t-1]-1, intraday = close[t]/open[t]-1
+    fn calculate_gap_analysis(series: &SeriesPayload) -> Result<GapAnalysis, AppError> {
+        let mut gap_series = Vec::new();
+        let mut intraday = Vec::new();
+        for t in 1..series.prices.len() {
+            if let Some(Some(open)) = series.opens.get(t) {
+                gap_series.push((series.dates[t].clone(), open / series.prices[t - 1] - 1.0));
+                intraday.push(series.prices[t] / open - 1.0);
+            }
+        }
+        if gap_series.is_empty() {
+            return Err(AppError::DataParsing("Open prices are not available".to_string()));
+        }
+        let n = gap_series.len() as f64;
+        Ok(GapAnalysis {
+            mean_overnight: gap_series.iter().map(|(_, g)| g).sum::<f64>() / n,
+            mean_intraday: intraday.iter().sum::<f64>() / n,
+            gap_series,
+        })
+    }
+}
+
+#[tauri::command]
+async fn gap_analysis(symbol: String, range: String, service: tauri::State<'_, YahooFinanceService>) -> Result<GapAnalysis, String> {
+    let result = service.get_financial_data(&symbol, &range, "1d").await
+        .and_then(|(series_payload, _)| YahooFinanceService::calculate_gap_analysis(&series_payload));
+    match result {
+        Ok(analysis) => Ok(analysis),
+        Err(e) => {
+            error!("gap_analysis error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- ボラティリティ・レジーム ----
+impl YahooFinanceService {
+    // 年率換算のローリング・ボラティリティ（returns は先頭プレースホルダー付き）
+    fn calculate_rolling_volatility(returns: &[f64], window: usize) -> Vec<Option<f64>> {
+        let mut result = vec![None; returns.len()];
+        if window < 2 { return result; }
+        for i in window..returns.len() {
+            let sample = &returns[i + 1 - window..=i];
+            let mean = sample.iter().sum::<f64>() / window as f64;
+            let var = sample.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (window - 1) as f64;
+            result[i] = Some(var.sqrt() * 252.0_f64.sqrt());
+        }
+        result
+    }
+
+    // 自身の履歴の33/66パーセンタイルでlow/normal/highに分類（ウォームアップ期間は除外）
+    fn classify_volatility_regimes(dates: &[String], returns: &[f64], window: usize) -> Vec<(String, String)> {
+        let volatility = Self::calculate_rolling_volatility(returns, window);
+        let mut history: Vec<f64> = volatility.iter().flatten().copied().collect();
+        history.sort_by(|a, b| a.total_cmp(b));
+        let low = Self::calculate_quantile(&history, 0.33);
+        let high = Self::calculate_quantile(&history, 0.66);
+
+        dates.iter().zip(&volatility)
+            .filter_map(|(date, vol)| vol.map(|v| {
+                let label = if v < low { "low" } else if v > high { "high" } else { "normal" };
+                (date.clone(), label.to_string())
+            }))
+            .collect()
+    }
+}
+
+#[tauri::command]
+async fn volatility_regime(
+    symbol: String, range: String, interval: String, window: usize,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<Vec<(String, String)>, String> {
+    if window < 2 {
+        return Err("window は2以上で指定してください".into());
+    }
+    match service.get_financial_data(&symbol, &range, &interval).await {
+        Ok((series_payload, analysis_result)) => Ok(YahooFinanceService::classify_volatility_regimes(&series_payload.dates, &analysis_result.returns, window)),
+        Err(e) => {
+            error!("volatility_regime error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- 古いキャッシュの一括再取得 ----
+#[derive(Serialize, Debug)]
+struct RefreshSummary {
+    refreshed: usize,
+    failed: usize,
+}
+
+impl SecureCacheManager {
+    // 期限切れでなくても max_age より古いエントリのキー
+    pub async fn keys_older_than(&self, max_age: Duration) -> Vec<String> {
+        let threshold = Utc::now() - max_age;
+        let mut keys = Vec::new();
+        for shard in &self.shards {
+            let store = shard.store.read().await;
+            keys.extend(store.iter()
+                .filter(|(_, data)| data.cached_at < threshold)
+                .map(|(key, _)| key.clone()));
+        }
+        keys
+    }
+}
+
+impl YahooFinanceService {
+    fn parse_cache_key(key: &str) -> Option<(&str, &str, &str)> {
+        let mut parts = key.rsplitn(3, ':');
+        let interval = parts.next()?;
+        let range = parts.next()?;
+        let symbol = parts.next()?;
+        Some((symbol, range, interval))
+    }
+
+    async fn refresh_stale(&self, max_age_minutes: i64) -> RefreshSummary {
+        let stale_keys = self.cache.keys_older_than(Duration::minutes(max_age_minutes)).await;
+        info!("Refreshing {} stale cache entries", stale_keys.len());
+
+        let results = futures::future::join_all(stale_keys.iter().map(|key| async move {
+            let (symbol, range, interval) = Self::parse_cache_key(key)
+                .ok_or_else(|| AppError::Cache(format!("Malformed cache key: {}", key)))?;
+            self.fetch_and_cache(symbol, range, interval).await
+        })).await;
+
+        let mut summary = RefreshSummary { refreshed: 0, failed: 0 };
+        for (key, result) in stale_keys.iter().zip(results) {
+            match result {
+                Ok(_) => summary.refreshed += 1,
+                Err(e) => {
+                    warn!("Failed to refresh {}: {}", key, e);
+                    summary.failed += 1;
+                }
+            }
+        }
+        summary
+    }
+}
+
+#[tauri::command]
+async fn refresh_stale(max_age_minutes: i64, service: tauri::State<'_, YahooFinanceService>) -> Result<RefreshSummary, String> {
+    if max_age_minutes < 0 {
+        return Err("max_age_minutes は0以上で指定してください".into());
+    }
+    let summary = service.refresh_stale(max_age_minutes).await;
+    info!("Stale cache refreshed: {} ok, {} failed", summary.refreshed, summary.failed);
+    Ok(summary)
+}
+
+// ---- 絶対リターンの自己相関（スピアマン） ----
+const MAX_AUTOCORR_LAGS: usize = 100;
+
+impl YahooFinanceService {
+    // 順位（同順位は平均順位）
+    fn calculate_ranks(values: &[f64]) -> Vec<f64> {
+        let mut order: Vec<usize> = (0..values.len()).collect();
+        order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+        let mut ranks = vec![0.0; values.len()];
+        let mut i = 0;
+        while i < order.len() {
+            let mut j = i;
+            while j + 1 < order.len() && values[order[j + 1]] == values[order[i]] { j += 1; }
+            let average = (i + j) as f64 / 2.0 + 1.0;
+            for &idx in &order[i..=j] { ranks[idx] = average; }
+            i = j + 1;
+        }
+        ranks
+    }
+
+    // |r_t| と |r_{t+k}| の順位相関。ボラティリティ・クラスタリング（ARCH効果）の指標
+    fn calculate_abs_return_autocorr(returns: &[f64], lags: usize) -> Vec<(usize, f64)> {
+        let abs_returns: Vec<f64> = returns.iter().map(|r| r.abs()).collect();
+        let max_lag = lags.min(MAX_AUTOCORR_LAGS).min(abs_returns.len().saturating_sub(3));
+        (1..=max_lag)
+            .map(|lag| {
+                let leading = Self::calculate_ranks(&abs_returns[..abs_returns.len() - lag]);
+                let lagged = Self::calculate_ranks(&abs_returns[lag..]);
+                (lag, Self::calculate_correlation(&leading, &lagged))
+            })
+            .collect()
+    }
+}
+
+#[tauri::command]
+async fn abs_return_autocorr(
+    symbol: String, range: String, interval: String, lags: usize,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<Vec<(usize, f64)>, String> {
+    match service.get_financial_data(&symbol, &range, &interval).await {
+        Ok((_, analysis_result)) => Ok(YahooFinanceService::calculate_abs_return_autocorr(&analysis_result.returns[1..], lags)),
+        Err(e) => {
+            error!("abs_return_autocorr error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- データ異常検知（売買停止・ゼロ出来高など） ----
+const FLAT_RUN_MIN_DAYS: usize = 5;
+const SNAP_MOVE_THRESHOLD: f64 = 0.05;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+struct Anomaly {
+    date: String,
+    #[serde(rename = "type")]
+    kind: String,
+    description: String,
+}
+
+impl YahooFinanceService {
+    fn detect_anomalies(series: &SeriesPayload) -> Vec<Anomaly> {
+        let prices = &series.prices;
+        let mut anomalies = Vec::new();
+
+        // 出来高ゼロの日
+        for (date, volume) in series.dates.iter().zip(&series.volumes) {
+            if *volume == Some(0.0) {
+                anomalies.push(Anomaly {
+                    date: date.clone(),
+                    kind: "zero_volume".to_string(),
+                    description: "出来高が0です".to_string(),
+                });
+            }
+        }
+
+        // 同一終値の連続（売買停止・上場廃止の疑い）
+        let mut run_start = 0;
+        for i in 1..=prices.len() {
+            if i < prices.len() && prices[i] == prices[run_start] { continue; }
+            let run_length = i - run_start;
+            if run_length >= FLAT_RUN_MIN_DAYS {
+                anomalies.push(Anomaly {
+                    date: series.dates[run_start].clone(),
+                    kind: "flat_run".to_string(),
+                    description: format!("終値{}が{}日連続で同一です（売買停止の可能性）", prices[run_start], run_length),
+                });
+            }
+            run_start = i;
+        }
+
+        // 1本だけ切りの良い価格へ飛んで戻るバー
+        for i in 1..prices.len().saturating_sub(1) {
+            let jump = prices[i] / prices[i - 1] - 1.0;
+            let revert = prices[i + 1] / prices[i] - 1.0;
+            if prices[i].fract() == 0.0 && jump.abs() > SNAP_MOVE_THRESHOLD && revert.abs() > SNAP_MOVE_THRESHOLD && jump.signum() != revert.signum() {
+                anomalies.push(Anomaly {
+                    date: series.dates[i].clone(),
+                    kind: "round_number_snap".to_string(),
+                    description: format!("終値が{}に一時的に飛んでいます（{:+.1}%→{:+.1}%）", prices[i], jump * 100.0, revert * 100.0),
+                });
+            }
+        }
+
+        anomalies.sort_by(|a, b| a.date.cmp(&b.date));
+        anomalies
+    }
+}
+
+#[tauri::command]
+async fn detect_anomalies(symbol: String, range: String, interval: String, service: tauri::State<'_, YahooFinanceService>) -> Result<Vec<Anomaly>, String> {
+    match service.get_financial_data(&symbol, &range, &interval).await {
+        Ok((series_payload, _)) => Ok(YahooFinanceService::detect_anomalies(&series_payload)),
+        Err(e) => {
+            error!("detect_anomalies error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- Newey-West 補正シャープレシオ ----
+#[derive(Serialize, Debug)]
+struct NeweyWestSharpe {
+    sharpe: f64,
+    se: f64,
+    tstat: f64,
+}
+
+impl YahooFinanceService {
+    // 年率シャープレシオとその標準誤差。
+    // SE = sqrt((LRV/γ0 + SR²/2) / T)、LRV = γ0 + 2Σ_{k=1..L} (1 - k/(L+1))·γk（Bartlett重み）
+    // lags = 0 なら i.i.d. を仮定した通常のSEになる
+    fn calculate_newey_west_sharpe(returns: &[f64], lags: usize) -> Result<NeweyWestSharpe, AppError> {
+        let t = returns.len();
+        if t < lags + 3 {
+            return Err(AppError::DataParsing("Insufficient data for Newey-West adjustment".to_string()));
+        }
+        let mean = returns.iter().sum::<f64>() / t as f64;
+        let autocovariance = |k: usize| -> f64 {
+            (k..t).map(|i| (returns[i] - mean) * (returns[i - k] - mean)).sum::<f64>() / t as f64
+        };
+        let gamma0 = autocovariance(0);
+        if gamma0 <= 0.0 {
+            return Err(AppError::DataParsing("Returns have zero variance".to_string()));
+        }
+        let long_run_variance = gamma0 + 2.0 * (1..=lags)
+            .map(|k| (1.0 - k as f64 / (lags + 1) as f64) * autocovariance(k))
+            .sum::<f64>();
+
+        let sharpe_daily = mean / gamma0.sqrt();
+        let se_daily = ((long_run_variance.max(0.0) / gamma0 + sharpe_daily.powi(2) / 2.0) / t as f64).sqrt();
+        let annualization = 252.0_f64.sqrt();
+        Ok(NeweyWestSharpe {
+            sharpe: sharpe_daily * annualization,
+            se: se_daily * annualization,
+            tstat: if se_daily > 0.0 { sharpe_daily / se_daily } else { 0.0 },
+        })
+    }
+}
+
+#[tauri::command]
+async fn sharpe_newey_west(
+    symbol: String, range: String, interval: String, lags: usize,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<NeweyWestSharpe, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(_, analysis_result)| YahooFinanceService::calculate_newey_west_sharpe(&analysis_result.returns[1..], lags));
+    match result {
+        Ok(sharpe) => Ok(sharpe),
+        Err(e) => {
+            error!("sharpe_newey_west error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- ネガティブキャッシュ（存在しないシンボル） ----
+impl YahooFinanceService {
+    pub fn set_negative_cache_minutes(&self, minutes: i64) {
+        self.negative_cache_minutes.store(minutes.max(0), Ordering::Relaxed);
+    }
+
+    async fn is_known_missing(&self, symbol: &str) -> bool {
+        let key = symbol.to_uppercase();
+        let now = Utc::now();
+        match self.negative_cache.read().await.get(&key) {
+            Some(expires_at) if *expires_at > now => return true,
+            Some(_) => {}
+            None => return false,
+        }
+        self.negative_cache.write().await.remove(&key);
+        false
+    }
+
+    // 確定的な not found のみ記憶する（通信エラーや5xxは一時的なので対象外）
+    async fn remember_missing(&self, symbol: &str) {
+        let minutes = self.negative_cache_minutes.load(Ordering::Relaxed);
+        if minutes == 0 { return; }
+        let expires_at = Utc::now() + Duration::minutes(minutes);
+        self.negative_cache.write().await.insert(symbol.to_uppercase(), expires_at);
+    }
+}
+
+// ---- レンジ・ボラティリティ（Parkinson / Garman-Klass） ----
+impl YahooFinanceService {
+    // 年率換算。Parkinson: σ² = mean(ln(H/L)²) / (4 ln2)
+    // Garman-Klass: σ² = mean(0.5·ln(H/L)² - (2 ln2 - 1)·ln(C/O)²)
+    fn calculate_range_volatility(series: &SeriesPayload, estimator: &str) -> Result<f64, AppError> {
+        let needs_open = match estimator {
+            "parkinson" => false,
+            "garman_klass" => true,
+            _ => return Err(AppError::InvalidParameter(format!("Unknown volatility estimator: {}", estimator))),
+        };
+
+        let mut terms = Vec::new();
+        for i in 0..series.prices.len() {
+            let (Some(Some(high)), Some(Some(low))) = (series.highs.get(i), series.lows.get(i)) else { continue };
+            let hl = (high / low).ln().powi(2);
+            if needs_open {
+                let Some(Some(open)) = series.opens.get(i) else { continue };
+                let co = (series.prices[i] / open).ln().powi(2);
+                terms.push(0.5 * hl - (2.0 * std::f64::consts::LN_2 - 1.0) * co);
+            } else {
+                terms.push(hl / (4.0 * std::f64::consts::LN_2));
+            }
+        }
+        if terms.is_empty() {
+            return Err(AppError::DataParsing(format!("OHLC data required for {} is not available", estimator)));
+        }
+        let variance = terms.iter().sum::<f64>() / terms.len() as f64;
+        Ok((variance.max(0.0) * 252.0).sqrt())
+    }
+}
+
+#[tauri::command]
+async fn range_volatility(
+    symbol: String, range: String, interval: String, estimator: String,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<f64, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(series_payload, _)| YahooFinanceService::calculate_range_volatility(&series_payload, &estimator));
+    match result {
+        Ok(volatility) => Ok(volatility),
+        Err(e) => {
+            error!("range_volatility error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- ストップロス／利確オーバーレイ ----
+#[derive(Serialize, Debug)]
+struct StopsResult {
+    exit_date: Option<String>,
+    exit_return: f64,
+}
+
+impl YahooFinanceService {
+    // 初日の終値で買い、損切り／利確ライン（0.05 = 5%）に触れたら決済する。
+    // 高値・安値があれば日中の到達で判定し、同じバーで両方に触れた場合は保守的に損切りを優先
+    fn calculate_stops_overlay(series: &SeriesPayload, stop_loss_pct: f64, take_profit_pct: f64) -> Result<StopsResult, AppError> {
+        if !(stop_loss_pct > 0.0 && stop_loss_pct < 1.0) || take_profit_pct <= 0.0 {
+            return Err(AppError::InvalidParameter("stop_loss_pct must be in (0, 1) and take_profit_pct positive".to_string()));
+        }
+        let prices = &series.prices;
+        let Some(&entry) = prices.first() else {
+            return Err(AppError::DataParsing("Insufficient data for stop overlay".to_string()));
+        };
+        let stop_price = entry * (1.0 - stop_loss_pct);
+        let target_price = entry * (1.0 + take_profit_pct);
+
+        for (i, &close) in prices.iter().enumerate().skip(1) {
+            let open = series.opens.get(i).copied().flatten();
+            let low = series.lows.get(i).copied().flatten();
+            let high = series.highs.get(i).copied().flatten();
+            // 高安がなければ終値で判定・約定し、ギャップで水準を越えた場合は始値で約定する
+            let fill = if low.unwrap_or(close) <= stop_price {
+                Some(if low.is_some() { open.map_or(stop_price, |o| o.min(stop_price)) } else { close })
+            } else if high.unwrap_or(close) >= target_price {
+                Some(if high.is_some() { open.map_or(target_price, |o| o.max(target_price)) } else { close })
+            } else {
+                None
+            };
+            if let Some(fill) = fill {
+                return Ok(StopsResult { exit_date: Some(series.dates[i].clone()), exit_return: fill / entry - 1.0 });
+            }
+        }
+        Ok(StopsResult { exit_date: None, exit_return: prices[prices.len() - 1] / entry - 1.0 })
+    }
+}
+
+#[tauri::command]
+async fn apply_stops(
+    symbol: String, range: String, interval: String, stop_loss_pct: f64, take_profit_pct: f64,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<StopsResult, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(series_payload, _)| YahooFinanceService::calculate_stops_overlay(&series_payload, stop_loss_pct, take_profit_pct));
+    match result {
+        Ok(stops) => Ok(stops),
+        Err(e) => {
+            error!("apply_stops error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- 相関の安定性 ----
+#[derive(Serialize, Debug)]
+struct CorrelationStability {
+    mean: f64,
+    std: f64,
+    min: f64,
+    max: f64,
+}
+
+impl YahooFinanceService {
+    // 末尾 window 本のリターンでのローリング相関（リターン列と同じ長さ）
+    fn calculate_rolling_correlation(x: &[f64], y: &[f64], window: usize) -> Vec<Option<f64>> {
+        let n = x.len().min(y.len());
+        let mut result = vec![None; n];
+        if window < 2 { return result; }
+        for i in window.saturating_sub(1)..n {
+            let start = i + 1 - window;
+            result[i] = Some(Self::calculate_correlation(&x[start..=i], &y[start..=i]));
+        }
+        result
+    }
+
+    fn calculate_correlation_stability(a: &SeriesPayload, b: &SeriesPayload, window: usize) -> Result<CorrelationStability, AppError> {
+        let (_, aligned) = Self::align_series(&[a, b]);
+        let returns_a = Self::calculate_simple_returns(&aligned[0]);
+        let returns_b = Self::calculate_simple_returns(&aligned[1]);
+        let rolling: Vec<f64> = Self::calculate_rolling_correlation(&returns_a, &returns_b, window).into_iter().flatten().collect();
+        if rolling.len() < 2 {
+            return Err(AppError::DataParsing("Insufficient overlapping data for rolling correlation".to_string()));
+        }
+        let n = rolling.len() as f64;
+        let mean = rolling.iter().sum::<f64>() / n;
+        Ok(CorrelationStability {
+            mean,
+            std: (rolling.iter().map(|c| (c - mean).powi(2)).sum::<f64>() / n).sqrt(),
+            min: rolling.iter().copied().fold(f64::MAX, f64::min),
+            max: rolling.iter().copied().fold(f64::MIN, f64::max),
+        })
+    }
+}
+
+#[tauri::command]
+async fn correlation_stability(
+    symbol_a: String, symbol_b: String, range: String, interval: String, window: usize,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<CorrelationStability, String> {
+    if window < 2 {
+        return Err("window は2以上で指定してください".into());
+    }
+    let result = async {
+        let (a, _) = service.get_financial_data(&symbol_a, &range, &interval).await?;
+        let (b, _) = service.get_financial_data(&symbol_b, &range, &interval).await?;
+        YahooFinanceService::calculate_correlation_stability(&a, &b, window)
+    }.await;
+    match result {
+        Ok(stability) => Ok(stability),
+        Err(e) => {
+            error!("correlation_stability error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- 取引カレンダーの欠落 ----
+impl YahooFinanceService {
+    // 間隔ごとの許容日数（日足なら週末を挟む3日まで）
+    fn expected_max_gap_days(interval: &str) -> i64 {
+        match interval {
+            "1wk" => 10,
+            "1mo" | "3mo" => 35,
+            _ => 3,
+        }
+    }
+
+    // 許容日数を超えて離れた連続する日付の組と、その日数
+    fn find_calendar_gaps(series: &SeriesPayload, max_gap_days: i64) -> Result<Vec<(String, String, i64)>, AppError> {
+        let mut gaps = Vec::new();
+        for pair in series.dates.windows(2) {
+            let days = (Self::parse_iso_date(&pair[1])? - Self::parse_iso_date(&pair[0])?).num_days();
+            if days > max_gap_days {
+                gaps.push((pair[0].clone(), pair[1].clone(), days));
+            }
+        }
+        Ok(gaps)
+    }
+}
+
+#[tauri::command]
+async fn calendar_gaps(symbol: String, range: String, interval: String, service: tauri::State<'_, YahooFinanceService>) -> Result<Vec<(String, String, i64)>, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(series_payload, _)| YahooFinanceService::find_calendar_gaps(&series_payload, YahooFinanceService::expected_max_gap_days(&interval)));
+    match result {
+        Ok(gaps) => Ok(gaps),
+        Err(e) => {
+            error!("calendar_gaps error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- 複合スコア（モメンタム・低ボラ・トレンド） ----
+const MAX_COMPOSITE_SYMBOLS: usize = 20;
+
+impl YahooFinanceService {
+    fn calculate_z_scores(values: &[f64]) -> Vec<f64> {
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let std = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt();
+        values.iter().map(|v| if std > 0.0 { (v - mean) / std } else { 0.0 }).collect()
+    }
+
+    // (モメンタム12-1, -年率ボラ, 終値/SMA200 - 1)。履歴が短い場合は利用可能な範囲で計算
+    fn calculate_factor_sub_scores(prices: &[f64]) -> Result<(f64, f64, f64), AppError> {
+        if prices.len() < 30 {
+            return Err(AppError::DataParsing("Insufficient data for composite score".to_string()));
+        }
+        let last = prices.len() - 1;
+        let skip_recent = last - 21;
+        let lookback_start = last.saturating_sub(252);
+        let momentum = prices[skip_recent] / prices[lookback_start] - 1.0;
+
+        let returns = Self::calculate_simple_returns(&prices[lookback_start..]);
+        let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+        let volatility = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / returns.len() as f64 * 252.0).sqrt();
+
+        let trend_window = &prices[prices.len().saturating_sub(200)..];
+        let sma = trend_window.iter().sum::<f64>() / trend_window.len() as f64;
+        Ok((momentum, -volatility, prices[last] / sma - 1.0))
+    }
+
+    // 各サブスコアを銘柄間でZスコア化し、平均を合成スコアとして降順に並べる
+    fn calculate_composite_scores(series: &[SeriesPayload]) -> Result<Vec<(String, f64)>, AppError> {
+        let sub_scores = series.iter()
+            .map(|s| Self::calculate_factor_sub_scores(&s.prices))
+            .collect::<Result<Vec<_>, _>>()?;
+        let momentum = Self::calculate_z_scores(&sub_scores.iter().map(|s| s.0).collect::<Vec<_>>());
+        let low_vol = Self::calculate_z_scores(&sub_scores.iter().map(|s| s.1).collect::<Vec<_>>());
+        let trend = Self::calculate_z_scores(&sub_scores.iter().map(|s| s.2).collect::<Vec<_>>());
+
+        let mut scores: Vec<(String, f64)> = series.iter().enumerate()
+            .map(|(i, s)| (s.symbol.clone(), (momentum[i] + low_vol[i] + trend[i]) / 3.0))
+            .collect();
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Ok(scores)
+    }
+}
+
+#[tauri::command]
+async fn composite_score(
+    symbols: Vec<String>, range: String, interval: String,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<Vec<(String, f64)>, String> {
+    if symbols.len() < 2 || symbols.len() > MAX_COMPOSITE_SYMBOLS {
+        return Err(format!("銘柄数は2〜{}件で指定してください", MAX_COMPOSITE_SYMBOLS));
+    }
+    let result = async {
+        let mut series = Vec::with_capacity(symbols.len());
+        for symbol in &symbols {
+            let (payload, _) = service.get_financial_data(symbol, &range, &interval).await?;
+            series.push(payload);
+        }
+        YahooFinanceService::calculate_composite_scores(&series)
+    }.await;
+    match result {
+        Ok(scores) => Ok(scores),
+        Err(e) => {
+            error!("composite_score error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- キャッシュ変更イベント（cache-changed） ----
+const CACHE_EVENT_DEBOUNCE_MS: u64 = 200;
+
+fn configure_cache_events(app: &tauri::AppHandle, service: &YahooFinanceService, enabled: bool) {
+    if !enabled {
+        service.cache.set_notifier(None);
+        return;
+    }
+    let handle = app.clone();
+    let listener: CacheListener = Arc::new(move |event: CacheChangedEvent| {
+        if let Err(e) = handle.emit("cache-changed", event) {
+            warn!("Failed to emit cache-changed event: {}", e);
+        }
+    });
+    service.cache.set_notifier(Some(CacheNotifier::new(listener, std::time::Duration::from_millis(CACHE_EVENT_DEBOUNCE_MS))));
+}
+
+// ---- 正規QQプロット ----
+impl YahooFinanceService {
+    // 標準正規分布の逆累積分布関数（Acklamの有理近似、相対誤差 ~1e-9）
+    fn inverse_normal_cdf(p: f64) -> f64 {
+        const A: [f64; 6] = [-3.969683028665376e+01, 2.209460984245205e+02, -2.759285104469687e+02, 1.38357751867269e+02, -3.066479806614716e+01, 2.506628277459239e+00];
+        const B: [f64; 5] = [-5.447609879822406e+01, 1.615858368580409e+02, -1.556989798598866e+02, 6.680131188771972e+01, -1.328068155288572e+01];
+        const C: [f64; 6] = [-7.784894002430293e-03, -3.223964580411365e-01, -2.400758277161838e+00, -2.549732539343734e+00, 4.374664141464968e+00, 2.938163982698783e+00];
+        const D: [f64; 4] = [7.784695709041462e-03, 3.224671290700398e-01, 2.445134137142996e+00, 3.754408661907416e+00];
+        const P_LOW: f64 = 0.02425;
+
+        if p <= 0.0 { return f64::NEG_INFINITY; }
+        if p >= 1.0 { return f64::INFINITY; }
+        if p < P_LOW {
+            let q = (-2.0 * p.ln()).sqrt();
+            (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5]) / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+        } else if p <= 1.0 - P_LOW {
+            let q = p - 0.5;
+            let r = q * q;
+            (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+        } else {
+            -Self::inverse_normal_cdf(1.0 - p)
+        }
+    }
+
+    // (理論正規分位点, 標準化したリターンの経験分位点) の組。正規ならy=x上に並ぶ
+    fn calculate_qq_normal(returns: &[f64]) -> Result<Vec<(f64, f64)>, AppError> {
+        if returns.len() < 3 {
+            return Err(AppError::InvalidParameter("Not enough returns for QQ plot".to_string()));
+        }
+        let n = returns.len() as f64;
+        let mean = returns.iter().sum::<f64>() / n;
+        let std = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+        if std <= 0.0 {
+            return Err(AppError::InvalidParameter("Returns have zero variance".to_string()));
+        }
+        let mut standardized: Vec<f64> = returns.iter().map(|r| (r - mean) / std).collect();
+        standardized.sort_by(|a, b| a.total_cmp(b));
+        // プロット位置は (i + 0.5) / n
+        Ok(standardized
+            .into_iter()
+            .enumerate()
+            .map(|(i, z)| (Self::inverse_normal_cdf((i as f64 + 0.5) / n), z))
+            .collect())
+    }
+}
+
+#[tauri::command]
+async fn qq_normal(symbol: String, range: String, interval: String, service: tauri::State<'_, YahooFinanceService>) -> Result<Vec<(f64, f64)>, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(_, analysis_result)| YahooFinanceService::calculate_qq_normal(&analysis_result.returns[1..]));
+    match result {
+        Ok(points) => Ok(points),
+        Err(e) => {
+            error!("qq_normal error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- 取引コスト控除後のリターン ----
+#[derive(Serialize, Debug)]
+struct NetStrategyReturn {
+    gross: f64,
+    net: f64,
+    total_cost: f64,
+}
+
+impl YahooFinanceService {
+    // 各期のポジション変化量（初期ポジションは0とみなす）
+    fn calculate_turnover(positions: &[f64]) -> Vec<f64> {
+        let mut previous = 0.0;
+        positions
+            .iter()
+            .map(|&p| {
+                let turnover = (p - previous).abs();
+                previous = p;
+                turnover
+            })
+            .collect()
+    }
+
+    // positions[i] を returns[i] の期間保有。コストは turnover × cost_bps / 10000 を各期に控除
+    fn calculate_net_strategy_return(positions: &[f64], returns: &[f64], cost_bps: f64) -> Result<NetStrategyReturn, AppError> {
+        if positions.len() != returns.len() || positions.is_empty() {
+            return Err(AppError::InvalidParameter("positions and returns must be non-empty and equal length".to_string()));
+        }
+        if !cost_bps.is_finite() || cost_bps < 0.0 {
+            return Err(AppError::InvalidParameter("cost_bps must be non-negative".to_string()));
+        }
+        let costs: Vec<f64> = Self::calculate_turnover(positions).into_iter().map(|t| t * cost_bps / 10_000.0).collect();
+        let mut gross_growth = 1.0;
+        let mut net_growth = 1.0;
+        for ((&p, &r), &c) in positions.iter().zip(returns).zip(&costs) {
+            gross_growth *= 1.0 + p * r;
+            net_growth *= 1.0 + p * r - c;
+        }
+        Ok(NetStrategyReturn { gross: gross_growth - 1.0, net: net_growth - 1.0, total_cost: costs.iter().sum() })
+    }
+}
+
+#[tauri::command]
+fn net_strategy_return(positions: Vec<f64>, returns: Vec<f64>, cost_bps: f64) -> Result<NetStrategyReturn, String> {
+    YahooFinanceService::calculate_net_strategy_return(&positions, &returns, cost_bps).map_err(|e| e.to_string())
+}
+
+// ---- 52週高値・安値からの距離 ----
+const MIN_PROXIMITY_POINTS: usize = 20;
+
+#[derive(Serialize, Debug)]
+struct HighLowProximity {
+    pct_from_52w_high: f64, // 0以下（高値更新中は0）
+    pct_from_52w_low: f64,  // 0以上
+    high: f64,
+    low: f64,
+    partial_window: bool,   // 1年分の履歴がなく、取得できた期間で計算した場合 true
+}
+
+impl YahooFinanceService {
+    // 最終日から遡って365暦日以内の終値で高値・安値を取る
+    fn calculate_high_low_proximity(dates: &[String], prices: &[f64]) -> Result<HighLowProximity, AppError> {
+        if prices.len() < MIN_PROXIMITY_POINTS || dates.len() != prices.len() {
+            return Err(AppError::DataParsing("Insufficient data for 52-week high/low".to_string()));
+        }
+        let latest_date = Self::parse_iso_date(&dates[dates.len() - 1])?;
+        let window_start = latest_date - chrono::Duration::days(365);
+        let mut start = 0;
+        for (i, date) in dates.iter().enumerate() {
+            if Self::parse_iso_date(date)? >= window_start {
+                start = i;
+                break;
+            }
+        }
+        let partial_window = Self::parse_iso_date(&dates[0])? > window_start;
+        let window = &prices[start..];
+        let high = window.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let low = window.iter().cloned().fold(f64::INFINITY, f64::min);
+        let latest = prices[prices.len() - 1];
+        Ok(HighLowProximity {
+            pct_from_52w_high: latest / high - 1.0,
+            pct_from_52w_low: latest / low - 1.0,
+            high,
+            low,
+            partial_window,
+        })
+    }
+}
+
+#[tauri::command]
+async fn high_low_proximity(symbol: String, range: String, interval: String, service: tauri::State<'_, YahooFinanceService>) -> Result<HighLowProximity, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(series_payload, _)| YahooFinanceService::calculate_high_low_proximity(&series_payload.dates, &series_payload.prices));
+    match result {
+        Ok(proximity) => Ok(proximity),
+        Err(e) => {
+            error!("high_low_proximity error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- 2資産の定期リバランス ----
+#[derive(Serialize, Debug)]
+struct RebalancedPortfolio {
+    dates: Vec<String>,
+    rebalanced: AnalysisResult,
+    buy_and_hold: AnalysisResult,
+}
+
+impl YahooFinanceService {
+    // 初期値1のポートフォリオ評価額。rebalance が None なら初期配分のまま放置（ドリフト）
+    fn simulate_two_asset_portfolio(dates: &[String], prices_a: &[f64], prices_b: &[f64], weight_a: f64, rebalance: Option<&str>) -> Result<Vec<f64>, AppError> {
+        if !(0.0..=1.0).contains(&weight_a) {
+            return Err(AppError::InvalidParameter("weight_a must be between 0 and 1".to_string()));
+        }
+        if prices_a.len() != prices_b.len() || dates.len() != prices_a.len() || prices_a.len() < 2 {
+            return Err(AppError::DataParsing("Insufficient aligned data for portfolio".to_string()));
+        }
+        let mut value_a = weight_a;
+        let mut value_b = 1.0 - weight_a;
+        let mut values = vec![1.0];
+        let mut last_period = match rebalance {
+            Some(frequency) => Some(Self::calendar_period(Self::parse_iso_date(&dates[0])?, frequency)?),
+            None => None,
+        };
+        for i in 1..prices_a.len() {
+            value_a *= prices_a[i] / prices_a[i - 1];
+            value_b *= prices_b[i] / prices_b[i - 1];
+            // 新しい期間の最初の取引日の終値で目標配分に戻す
+            if let Some(frequency) = rebalance {
+                let period = Some(Self::calendar_period(Self::parse_iso_date(&dates[i])?, frequency)?);
+                if period != last_period {
+                    last_period = period;
+                    let total = value_a + value_b;
+                    value_a = total * weight_a;
+                    value_b = total * (1.0 - weight_a);
+                }
+            }
+            values.push(value_a + value_b);
+        }
+        Ok(values)
+    }
+
+    fn calculate_rebalanced_portfolio(&self, a: &SeriesPayload, b: &SeriesPayload, weight_a: f64, rebalance: &str) -> Result<RebalancedPortfolio, AppError> {
+        let (dates, aligned) = Self::align_series(&[a, b]);
+        let rebalanced = Self::simulate_two_asset_portfolio(&dates, &aligned[0], &aligned[1], weight_a, Some(rebalance))?;
+        let drift = Self::simulate_two_asset_portfolio(&dates, &aligned[0], &aligned[1], weight_a, None)?;
+        Ok(RebalancedPortfolio {
+            rebalanced: self.analyze_financial_data(&rebalanced)?,
+            buy_and_hold: self.analyze_financial_data(&drift)?,
+            dates,
+        })
+    }
+}
+
+#[tauri::command]
+async fn rebalanced_portfolio(
+    symbol_a: String, weight_a: f64, symbol_b: String, range: String, interval: String, rebalance: String,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<RebalancedPortfolio, String> {
+    let result = async {
+        let (a, _) = service.get_financial_data(&symbol_a, &range, &interval).await?;
+        let (b, _) = service.get_financial_data(&symbol_b, &range, &interval).await?;
+        service.calculate_rebalanced_portfolio(&a, &b, weight_a, &rebalance)
+    }.await;
+    match result {
+        Ok(portfolio) => Ok(portfolio),
+        Err(e) => {
+            error!("rebalanced_portfolio error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- リターン分布のエントロピー ----
+const MAX_ENTROPY_BINS: usize = 1000;
+
+impl YahooFinanceService {
+    // 最小〜最大を等幅に分けたヒストグラムのシャノンエントロピーを ln(bins) で正規化（0〜1）
+    fn calculate_return_entropy(returns: &[f64], bins: usize) -> Result<f64, AppError> {
+        if !(2..=MAX_ENTROPY_BINS).contains(&bins) {
+            return Err(AppError::InvalidParameter(format!("bins must be between 2 and {}", MAX_ENTROPY_BINS)));
+        }
+        if returns.is_empty() {
+            return Err(AppError::DataParsing("Insufficient data for entropy".to_string()));
+        }
+        let min = returns.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = returns.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        if max <= min {
+            return Ok(0.0);
+        }
+        let width = (max - min) / bins as f64;
+        let mut counts = vec![0usize; bins];
+        for &r in returns {
+            let bin = (((r - min) / width) as usize).min(bins - 1);
+            counts[bin] += 1;
+        }
+        let n = returns.len() as f64;
+        let entropy: f64 = counts
+            .iter()
+            .filter(|&&c| c > 0)
+            .map(|&c| {
+                let p = c as f64 / n;
+                -p * p.ln()
+            })
+            .sum();
+        Ok(entropy / (bins as f64).ln())
+    }
+}
+
+#[tauri::command]
+async fn return_entropy(symbol: String, range: String, interval: String, bins: usize, service: tauri::State<'_, YahooFinanceService>) -> Result<f64, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(_, analysis_result)| YahooFinanceService::calculate_return_entropy(&analysis_result.returns[1..], bins));
+    match result {
+        Ok(entropy) => Ok(entropy),
+        Err(e) => {
+            error!("return_entropy error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- 共和分検定（Engle-Granger） ----
+// 2変数・定数項ありの Engle-Granger 5% 臨界値（MacKinnon）
+const ENGLE_GRANGER_CRITICAL_5PCT: f64 = -3.34;
+const ADF_LAGS: usize = 1;
+
+#[derive(Serialize, Debug)]
+struct CointegrationTest {
+    beta: f64,
+    adf_stat: f64,
+    is_cointegrated: bool,
+}
+
+impl YahooFinanceService {
+    // Δe_t = c + γ·e_{t-1} + Σ φ_j·Δe_{t-j} を回帰し、γ の t 値を返す
+    fn calculate_adf_statistic(series: &[f64], lags: usize) -> Result<f64, AppError> {
+        let diffs: Vec<f64> = series.windows(2).map(|w| w[1] - w[0]).collect();
+        let k = lags + 2; // 定数項, e_{t-1}, ラグ差分
+        let rows: Vec<(f64, Vec<f64>)> = (lags..diffs.len())
+            .map(|t| {
+                let mut x = vec![1.0, series[t]];
+                x.extend((1..=lags).map(|j| diffs[t - j]));
+                (diffs[t], x)
+            })
+            .collect();
+        if rows.len() <= k + 1 {
+            return Err(AppError::DataParsing("Insufficient data for ADF test".to_string()));
+        }
+
+        let mut xtx = vec![vec![0.0; k]; k];
+        let mut xty = vec![0.0; k];
+        for (y, x) in &rows {
+            for a in 0..k {
+                xty[a] += x[a] * y;
+                for b in 0..k {
+                    xtx[a][b] += x[a] * x[b];
+                }
+            }
+        }
+        let singular = || AppError::DataParsing("Singular ADF regression".to_string());
+        let coefficients = Self::solve_linear_system(xtx.clone(), xty).ok_or_else(singular)?;
+        // (X'X)^-1 の γ 対角成分
+        let mut unit = vec![0.0; k];
+        unit[1] = 1.0;
+        let inverse_column = Self::solve_linear_system(xtx, unit).ok_or_else(singular)?;
+
+        let ss_res: f64 = rows
+            .iter()
+            .map(|(y, x)| (y - x.iter().zip(&coefficients).map(|(a, b)| a * b).sum::<f64>()).powi(2))
+            .sum();
+        let sigma2 = ss_res / (rows.len() - k) as f64;
+        let standard_error = (sigma2 * inverse_column[1]).sqrt();
+        if standard_error.is_nan() || standard_error <= 0.0 {
+            return Err(singular());
+        }
+        Ok(coefficients[1] / standard_error)
+    }
+
+    // 対数価格で A を B に回帰し、残差に ADF 検定をかける
+    fn calculate_cointegration(a: &SeriesPayload, b: &SeriesPayload) -> Result<CointegrationTest, AppError> {
+        let (_, aligned) = Self::align_series(&[a, b]);
+        if aligned[0].iter().chain(&aligned[1]).any(|&p| p <= 0.0) {
+            return Err(AppError::DataParsing("Prices must be positive for cointegration test".to_string()));
+        }
+        let log_a: Vec<f64> = aligned[0].iter().map(|p| p.ln()).collect();
+        let log_b: Vec<f64> = aligned[1].iter().map(|p| p.ln()).collect();
+        let (coefficients, _) = Self::calculate_ols(&log_a, std::slice::from_ref(&log_b))?;
+        let residuals: Vec<f64> = log_a
+            .iter()
+            .zip(&log_b)
+            .map(|(y, x)| y - coefficients[0] - coefficients[1] * x)
+            .collect();
+        let adf_stat = Self::calculate_adf_statistic(&residuals, ADF_LAGS)?;
+        Ok(CointegrationTest {
+            beta: coefficients[1],
+            adf_stat,
+            is_cointegrated: adf_stat < ENGLE_GRANGER_CRITICAL_5PCT,
+        })
+    }
+}
+
+#[tauri::command]
+async fn cointegration_test(
+    symbol_a: String, symbol_b: String, range: String, interval: String,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<CointegrationTest, String> {
+    let result = async {
+        let (a, _) = service.get_financial_data(&symbol_a, &range, &interval).await?;
+        let (b, _) = service.get_financial_data(&symbol_b, &range, &interval).await?;
+        YahooFinanceService::calculate_cointegration(&a, &b)
+    }.await;
+    match result {
+        Ok(test) => Ok(test),
+        Err(e) => {
+            error!("cointegration_test error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- データ品質レポート ----
+// 祝日を考慮した年間取引日数 / 年間平日数の近似
+const TRADING_DAYS_PER_WEEKDAY: f64 = 252.0 / 261.0;
+
+#[derive(Serialize, Debug)]
+struct DataQualityReport {
+    expected_points: usize,
+    actual_points: usize,
+    dropped_nulls: usize,
+    gaps: usize,
+    quality_score: f64, // 0〜1
+}
+
+impl YahooFinanceService {
+    // 期間の暦日数（最終日から遡る）。max は取得できた全期間
+    fn range_calendar_days(range: &str, first: chrono::NaiveDate, last: chrono::NaiveDate) -> i64 {
+        match range {
+            "1mo" => 30,
+            "3mo" => 91,
+            "6mo" => 182,
+            "1y" => 365,
+            "2y" => 730,
+            "5y" => 1826,
+            "10y" => 3652,
+            "ytd" => last.ordinal() as i64,
+            _ => (last - first).num_days() + 1,
+        }
+    }
+
+    // 期間と間隔から期待されるデータ点数を取引カレンダーの近似で求める
+    fn expected_points(range: &str, interval: &str, first: chrono::NaiveDate, last: chrono::NaiveDate) -> usize {
+        let days = Self::range_calendar_days(range, first, last);
+        let expected = match interval {
+            "1wk" => days as f64 / 7.0,
+            "1mo" => days as f64 / 30.44,
+            "3mo" => days as f64 / 91.31,
+            _ => {
+                let weekdays = (0..days)
+                    .map(|offset| last - chrono::Duration::days(offset))
+                    .filter(|d| d.weekday().number_from_monday() <= 5)
+                    .count();
+                weekdays as f64 * TRADING_DAYS_PER_WEEKDAY
+            }
+        };
+        expected.round().max(1.0) as usize
+    }
+
+    // スコア = カバー率 × (1 - null比率) × (1 - ギャップ比率)
+    fn calculate_data_quality(series: &SeriesPayload, range: &str, interval: &str) -> Result<DataQualityReport, AppError> {
+        let (Some(first), Some(last)) = (series.dates.first(), series.dates.last()) else {
+            return Err(AppError::DataParsing("No data for quality report".to_string()));
+        };
+        let expected_points = Self::expected_points(range, interval, Self::parse_iso_date(first)?, Self::parse_iso_date(last)?);
+        let actual_points = series.prices.len();
+        let gaps = Self::find_calendar_gaps(series, Self::expected_max_gap_days(interval))?.len();
+
+        let coverage = (actual_points as f64 / expected_points as f64).min(1.0);
+        let null_ratio = series.dropped_nulls as f64 / (actual_points + series.dropped_nulls) as f64;
+        let gap_ratio = gaps as f64 / actual_points.saturating_sub(1).max(1) as f64;
+        Ok(DataQualityReport {
+            expected_points,
+            actual_points,
+            dropped_nulls: series.dropped_nulls,
+            gaps,
+            quality_score: (coverage * (1.0 - null_ratio) * (1.0 - gap_ratio)).clamp(0.0, 1.0),
+        })
+    }
+}
+
+#[tauri::command]
+async fn data_quality_report(symbol: String, range: String, interval: String, service: tauri::State<'_, YahooFinanceService>) -> Result<DataQualityReport, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(series_payload, _)| YahooFinanceService::calculate_data_quality(&series_payload, &range, &interval));
+    match result {
+        Ok(report) => Ok(report),
+        Err(e) => {
+            error!("data_quality_report error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- 対数価格の線形トレンド ----
+#[derive(Serialize, Debug)]
+struct TrendFit {
+    slope_per_day: f64,    // 暦日あたりの対数価格の傾き
+    annualized_trend: f64, // exp(slope × 365) - 1
+    r_squared: f64,
+}
+
+impl YahooFinanceService {
+    fn calculate_trend_fit(dates: &[String], prices: &[f64]) -> Result<TrendFit, AppError> {
+        if dates.len() != prices.len() || prices.iter().any(|&p| p <= 0.0) {
+            return Err(AppError::DataParsing("Trend fit requires positive prices aligned with dates".to_string()));
+        }
+        let Some(first) = dates.first() else {
+            return Err(AppError::DataParsing("Insufficient data for trend fit".to_string()));
+        };
+        let first = Self::parse_iso_date(first)?;
+        let days = dates
+            .iter()
+            .map(|d| Ok((Self::parse_iso_date(d)? - first).num_days() as f64))
+            .collect::<Result<Vec<f64>, AppError>>()?;
+        let log_prices: Vec<f64> = prices.iter().map(|p| p.ln()).collect();
+        let (coefficients, r_squared) = Self::calculate_ols(&log_prices, &[days])?;
+        Ok(TrendFit {
+            slope_per_day: coefficients[1],
+            annualized_trend: (coefficients[1] * 365.0).exp() - 1.0,
+            r_squared,
+        })
+    }
+}
+
+#[tauri::command]
+async fn trend_fit(symbol: String, range: String, interval: String, service: tauri::State<'_, YahooFinanceService>) -> Result<TrendFit, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(series_payload, _)| YahooFinanceService::calculate_trend_fit(&series_payload.dates, &series_payload.prices));
+    match result {
+        Ok(fit) => Ok(fit),
+        Err(e) => {
+            error!("trend_fit error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- ローリング・ソルティノレシオ ----
+const ROLLING_SORTINO_WINDOW: usize = 60;
+
+impl YahooFinanceService {
+    // 年率ソルティノ。下方偏差は target を下回った分の二乗平均（全観測数で割る）
+    // 下方リターンがない場合は定義できないので None
+    fn calculate_sortino(returns: &[f64], target_daily: f64) -> Option<f64> {
+        if returns.is_empty() { return None; }
+        let n = returns.len() as f64;
+        let mean_excess = returns.iter().map(|r| r - target_daily).sum::<f64>() / n;
+        let downside = (returns.iter().map(|r| (r - target_daily).min(0.0).powi(2)).sum::<f64>() / n).sqrt();
+        if downside > 0.0 { Some(mean_excess / downside * 252.0_f64.sqrt()) } else { None }
+    }
+
+    // returns は analysis.returns と同じ並び（先頭のプレースホルダーは窓に含めない）
+    fn calculate_rolling_sortino(returns: &[f64], window: usize, target_daily: f64) -> Vec<Option<f64>> {
+        let mut result = vec![None; returns.len()];
+        if window < 2 { return result; }
+        for i in window..returns.len() {
+            result[i] = Self::calculate_sortino(&returns[i + 1 - window..=i], target_daily);
+        }
+        result
+    }
+}
+
+struct RollingSortinoIndicator {
+    name: String,
+    window: usize,
+    target_daily: f64,
+}
+
+impl RollingSortinoIndicator {
+    fn new(window: usize, target_daily: f64) -> Self {
+        Self { name: format!("rolling_sortino{}", window), window, target_daily }
+    }
+}
+
+impl Indicator for RollingSortinoIndicator {
+    fn name(&self) -> &str { &self.name }
+    fn compute(&self, ctx: &SeriesContext) -> IndicatorOutput {
+        IndicatorOutput::Series(YahooFinanceService::calculate_rolling_sortino(&ctx.returns, self.window, self.target_daily))
+    }
+}
+
+// ---- InfluxDB ラインプロトコル出力 ----
+const INFLUX_MEASUREMENT: &str = "skew_prices";
+
+impl YahooFinanceService {
+    // タグのキー・値ではカンマ・空白・等号をエスケープする
+    fn escape_influx_tag(value: &str) -> String {
+        value.replace('\\', "\\\\").replace(',', "\\,").replace(' ', "\\ ").replace('=', "\\=")
+    }
+
+    // 1日1行。SMA が未定義の日はそのフィールドを省く。時刻は UTC 0時のナノ秒
+    fn render_influx_lines(symbol: &str, series: &SeriesPayload, analysis: &AnalysisResult) -> Result<String, AppError> {
+        let n = series.dates.len();
+        if series.prices.len() != n || analysis.returns.len() != n || analysis.sma5.len() != n || analysis.sma20.len() != n {
+            return Err(AppError::InvalidParameter("series and analysis lengths do not match".to_string()));
+        }
+        let tag = Self::escape_influx_tag(symbol);
+        let mut out = String::new();
+        for i in 0..n {
+            let timestamp = Self::parse_iso_date(&series.dates[i])?
+                .and_hms_opt(0, 0, 0)
+                .and_then(|dt| dt.and_utc().timestamp_nanos_opt())
+                .ok_or_else(|| AppError::DataParsing(format!("Date out of range: {}", series.dates[i])))?;
+            let mut fields = vec![format!("close={}", series.prices[i]), format!("return={}", analysis.returns[i])];
+            if let Some(v) = analysis.sma5[i] { fields.push(format!("sma5={}", v)); }
+            if let Some(v) = analysis.sma20[i] { fields.push(format!("sma20={}", v)); }
+            out.push_str(&format!("{},symbol={} {} {}\n", INFLUX_MEASUREMENT, tag, fields.join(","), timestamp));
+        }
+        Ok(out)
+    }
+}
+
+#[tauri::command]
+fn export_influx_line(symbol: String, series: SeriesPayload, analysis: AnalysisResult, output_path: String) -> Result<String, String> {
+    let lines = YahooFinanceService::render_influx_lines(&symbol, &series, &analysis).map_err(|e| e.to_string())?;
+    std::fs::write(&output_path, lines).map_err(|e| e.to_string())?;
+    Ok(output_path)
+}
+
+// ---- 上昇・下落局面別のベータ ----
+#[derive(Serialize, Debug)]
+struct AsymmetricBeta {
+    up_beta: f64,
+    down_beta: f64,
+    up_days: usize,
+    down_days: usize,
+}
+
+impl YahooFinanceService {
+    // ベンチマークが上昇した日と下落した日に分けてそれぞれベータを推定する（変化なしの日は除外）
+    fn calculate_asymmetric_beta(asset: &SeriesPayload, benchmark: &SeriesPayload) -> Result<AsymmetricBeta, AppError> {
+        let (_, aligned) = Self::align_series(&[asset, benchmark]);
+        let asset_returns = Self::calculate_simple_returns(&aligned[0]);
+        let benchmark_returns = Self::calculate_simple_returns(&aligned[1]);
+        let pairs: Vec<(f64, f64)> = asset_returns.into_iter().zip(benchmark_returns).collect();
+        let up: Vec<(f64, f64)> = pairs.iter().copied().filter(|&(_, b)| b > 0.0).collect();
+        let down: Vec<(f64, f64)> = pairs.iter().copied().filter(|&(_, b)| b < 0.0).collect();
+        if up.len() < 3 || down.len() < 3 {
+            return Err(AppError::DataParsing("Not enough up and down benchmark days".to_string()));
+        }
+        let beta_of = |pairs: &[(f64, f64)]| {
+            let (a, b): (Vec<f64>, Vec<f64>) = pairs.iter().copied().unzip();
+            Self::calculate_beta(&a, &b)
+        };
+        Ok(AsymmetricBeta { up_beta: beta_of(&up), down_beta: beta_of(&down), up_days: up.len(), down_days: down.len() })
+    }
+}
+
+#[tauri::command]
+async fn asymmetric_beta(
+    symbol: String, benchmark: String, range: String, interval: String,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<AsymmetricBeta, String> {
+    let result = async {
+        let (asset, _) = service.get_financial_data(&symbol, &range, &interval).await?;
+        let (bench, _) = service.get_financial_data(&benchmark, &range, &interval).await?;
+        YahooFinanceService::calculate_asymmetric_beta(&asset, &bench)
+    }.await;
+    match result {
+        Ok(beta) => Ok(beta),
+        Err(e) => {
+            error!("asymmetric_beta error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- N日リターンの分布 ----
+#[derive(Serialize, Debug)]
+struct RollingReturnDistribution {
+    mean: f64,
+    std: f64,
+    skew: f64,
+    p5: f64,
+    p95: f64,
+}
+
+impl YahooFinanceService {
+    // 重なりのある N 期間リターン（prices[i + window] / prices[i] - 1）の要約統計
+    fn calculate_rolling_return_distribution(prices: &[f64], window: usize) -> Result<RollingReturnDistribution, AppError> {
+        if window == 0 {
+            return Err(AppError::InvalidParameter("window must be positive".to_string()));
+        }
+        if prices.len() < window + 2 {
+            return Err(AppError::DataParsing("Insufficient data for rolling return distribution".to_string()));
+        }
+        let mut returns: Vec<f64> = prices.iter().zip(&prices[window..]).map(|(start, end)| end / start - 1.0).collect();
+        let n = returns.len() as f64;
+        let mean = returns.iter().sum::<f64>() / n;
+        let std = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+        let skew = Self::calculate_skewness(&returns);
+        returns.sort_by(|a, b| a.total_cmp(b));
+        Ok(RollingReturnDistribution {
+            mean,
+            std,
+            skew,
+            p5: Self::calculate_quantile(&returns, 0.05),
+            p95: Self::calculate_quantile(&returns, 0.95),
+        })
+    }
+}
+
+#[tauri::command]
+async fn rolling_return_distribution(
+    symbol: String, range: String, interval: String, window: usize,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<RollingReturnDistribution, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(series_payload, _)| YahooFinanceService::calculate_rolling_return_distribution(&series_payload.prices, window));
+    match result {
+        Ok(distribution) => Ok(distribution),
+        Err(e) => {
+            error!("rolling_return_distribution error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- 2時点間の指標の変化 ----
+#[derive(Serialize, Debug, Clone)]
+struct MetricsSnapshot {
+    as_of: String,
+    mean_return_daily: f64,
+    std_return_daily: f64,
+    sharpe_annual: f64,
+    skew: f64,
+    max_drawdown: f64,
+    annualized_return: f64,
+}
+
+#[derive(Serialize, Debug)]
+struct MetricsDelta {
+    a: MetricsSnapshot,
+    b: MetricsSnapshot,
+    // 各指標の b - a
+    mean_return_daily: f64,
+    std_return_daily: f64,
+    sharpe_annual: f64,
+    skew: f64,
+    max_drawdown: f64,
+    annualized_return: f64,
+}
+
+impl YahooFinanceService {
+    // as_of 以前（当日を含む）の系列で指標を計算する
+    fn calculate_metrics_as_of(dates: &[String], prices: &[f64], as_of: &str) -> Result<MetricsSnapshot, AppError> {
+        let target = Self::parse_iso_date(as_of)?;
+        let (Some(first), Some(last)) = (dates.first(), dates.last()) else {
+            return Err(AppError::DataParsing("No data for metrics".to_string()));
+        };
+        if target < Self::parse_iso_date(first)? || target > Self::parse_iso_date(last)? {
+            return Err(AppError::InvalidParameter(format!("{} is outside the series ({} to {})", as_of, first, last)));
+        }
+        let mut end = 0;
+        for (i, date) in dates.iter().enumerate() {
+            if Self::parse_iso_date(date)? > target { break; }
+            end = i + 1;
+        }
+        let prices = &prices[..end];
+        if prices.len() < 3 {
+            return Err(AppError::DataParsing(format!("Insufficient data up to {}", as_of)));
+        }
+        let returns = Self::calculate_simple_returns(prices);
+        let n = returns.len() as f64;
+        let mean = returns.iter().sum::<f64>() / n;
+        Ok(MetricsSnapshot {
+            as_of: as_of.to_string(),
+            mean_return_daily: mean,
+            std_return_daily: (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n).sqrt(),
+            sharpe_annual: Self::calculate_annualized_sharpe(&returns),
+            skew: Self::calculate_skewness(&returns),
+            max_drawdown: Self::calculate_max_drawdown(prices),
+            annualized_return: Self::calculate_annualized_return(prices),
+        })
+    }
+
+    fn calculate_metrics_delta(dates: &[String], prices: &[f64], as_of_a: &str, as_of_b: &str) -> Result<MetricsDelta, AppError> {
+        let a = Self::calculate_metrics_as_of(dates, prices, as_of_a)?;
+        let b = Self::calculate_metrics_as_of(dates, prices, as_of_b)?;
+        Ok(MetricsDelta {
+            mean_return_daily: b.mean_return_daily - a.mean_return_daily,
+            std_return_daily: b.std_return_daily - a.std_return_daily,
+            sharpe_annual: b.sharpe_annual - a.sharpe_annual,
+            skew: b.skew - a.skew,
+            max_drawdown: b.max_drawdown - a.max_drawdown,
+            annualized_return: b.annualized_return - a.annualized_return,
+            a,
+            b,
+        })
+    }
+}
+
+#[tauri::command]
+async fn metrics_delta(
+    symbol: String, range: String, interval: String, as_of_a: String, as_of_b: String,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<MetricsDelta, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(series_payload, _)| YahooFinanceService::calculate_metrics_delta(&series_payload.dates, &series_payload.prices, &as_of_a, &as_of_b));
+    match result {
+        Ok(delta) => Ok(delta),
+        Err(e) => {
+            error!("metrics_delta error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- リスク寄与度 ----
+const MAX_PORTFOLIO_HOLDINGS: usize = 20;
+
+impl YahooFinanceService {
+    // 標本共分散行列（年率換算）
+    fn calculate_covariance_matrix(returns: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        let n = returns.first().map_or(0, Vec::len);
+        let means: Vec<f64> = returns.iter().map(|r| r.iter().sum::<f64>() / n as f64).collect();
+        (0..returns.len())
+            .map(|i| {
+                (0..returns.len())
+                    .map(|j| {
+                        let cov: f64 = returns[i].iter().zip(&returns[j]).map(|(a, b)| (a - means[i]) * (b - means[j])).sum();
+                        cov / (n - 1) as f64 * 252.0
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // 寄与度_i = w_i × (Σw)_i / σ_p。合計はポートフォリオの年率ボラティリティに一致する
+    fn calculate_risk_contributions(series: &[SeriesPayload], weights: &[f64]) -> Result<Vec<f64>, AppError> {
+        let refs: Vec<&SeriesPayload> = series.iter().collect();
+        let (_, aligned) = Self::align_series(&refs);
+        if aligned.first().map_or(0, Vec::len) < 3 {
+            return Err(AppError::DataParsing("Insufficient overlapping data".to_string()));
+        }
+        let returns: Vec<Vec<f64>> = aligned.iter().map(|p| Self::calculate_simple_returns(p)).collect();
+        let covariance = Self::calculate_covariance_matrix(&returns);
+        let marginal: Vec<f64> = covariance
+            .iter()
+            .map(|row| row.iter().zip(weights).map(|(c, w)| c * w).sum())
+            .collect();
+        let variance: f64 = weights.iter().zip(&marginal).map(|(w, m)| w * m).sum();
+        if variance <= 0.0 {
+            return Err(AppError::DataParsing("Portfolio has zero variance".to_string()));
+        }
+        let vol = variance.sqrt();
+        Ok(weights.iter().zip(&marginal).map(|(w, m)| w * m / vol).collect())
+    }
+}
+
+#[tauri::command]
+async fn risk_contributions(
+    holdings: Vec<(String, f64)>, range: String, interval: String,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<Vec<(String, f64)>, String> {
+    if holdings.len() < 2 || holdings.len() > MAX_PORTFOLIO_HOLDINGS {
+        return Err(format!("保有銘柄数は2〜{}件で指定してください", MAX_PORTFOLIO_HOLDINGS));
+    }
+    let result = async {
+        let mut series = Vec::with_capacity(holdings.len());
+        for (symbol, _) in &holdings {
+            let (payload, _) = service.get_financial_data(symbol, &range, &interval).await?;
+            series.push(payload);
+        }
+        let weights: Vec<f64> = holdings.iter().map(|(_, w)| *w).collect();
+        YahooFinanceService::calculate_risk_contributions(&series, &weights)
+    }.await;
+    match result {
+        Ok(contributions) => Ok(holdings.into_iter().map(|(symbol, _)| symbol).zip(contributions).collect()),
+        Err(e) => {
+            error!("risk_contributions error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- Prometheus テキスト形式のスナップショット ----
+impl SecureCacheManager {
+    // 期限切れを含む全エントリの複製（診断・出力用）
+    async fn snapshot_entries(&self) -> Vec<(String, CachedData)> {
+        let mut entries = Vec::new();
+        for shard in &self.shards {
+            let store = shard.store.read().await;
+            entries.extend(store.iter().map(|(key, data)| (key.clone(), data.clone())));
+        }
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries
+    }
+}
+
+impl YahooFinanceService {
+    // ラベル値ではバックスラッシュ・ダブルクォート・改行をエスケープする
+    fn escape_prometheus_label(value: &str) -> String {
+        value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+    }
+
+    fn render_prometheus(telemetry: &TelemetrySnapshot, entries: &[(String, CachedData)]) -> String {
+        let mut out = String::new();
+        let mut family = |name: &str, kind: &str, help: &str, samples: Vec<(String, f64)>| {
+            out.push_str(&format!("# HELP {} {}\n# TYPE {} {}\n", name, help, name, kind));
+            for (labels, value) in samples {
+                out.push_str(&format!("{}{} {}\n", name, labels, value));
+            }
+        };
+        family("skew_fetches_total", "counter", "Total data requests.", vec![(String::new(), telemetry.total_fetches as f64)]);
+        family("skew_cache_hits_total", "counter", "Requests served from cache.", vec![(String::new(), telemetry.cache_hits as f64)]);
+        family("skew_cache_misses_total", "counter", "Requests that missed the cache.", vec![(String::new(), telemetry.cache_misses as f64)]);
+        let mut errors: Vec<(&String, &u64)> = telemetry.network_errors.iter().collect();
+        errors.sort();
+        family(
+            "skew_network_errors_total", "counter", "Network errors by code.",
+            errors.into_iter().map(|(code, count)| (format!("{{code=\"{}\"}}", Self::escape_prometheus_label(code)), *count as f64)).collect(),
+        );
+        family("skew_fetch_latency_avg_ms", "gauge", "Average network fetch latency in milliseconds.", vec![(String::new(), telemetry.avg_fetch_latency_ms)]);
+
+        // 銘柄ごとの最新指標（キャッシュ中のエントリから）
+        let labels: Vec<String> = entries
+            .iter()
+            .map(|(key, data)| {
+                let (_, range, interval) = Self::parse_cache_key(key).unwrap_or(("", "", ""));
+                format!(
+                    "{{symbol=\"{}\",range=\"{}\",interval=\"{}\"}}",
+                    Self::escape_prometheus_label(&data.data.symbol),
+                    Self::escape_prometheus_label(range),
+                    Self::escape_prometheus_label(interval)
+                )
+            })
+            .collect();
+        let per_symbol = |value: &dyn Fn(&CachedData) -> f64| -> Vec<(String, f64)> {
+            labels.iter().cloned().zip(entries.iter().map(|(_, data)| value(data))).collect()
+        };
+        family("skew_last_price", "gauge", "Latest close price.", per_symbol(&|d| d.data.prices.last().copied().unwrap_or(f64::NAN)));
+        family("skew_sharpe_annual", "gauge", "Annualized Sharpe ratio.", per_symbol(&|d| d.analysis.sharpe_annual));
+        family("skew_std_return_daily", "gauge", "Daily return standard deviation.", per_symbol(&|d| d.analysis.std_return_daily));
+        out
+    }
+}
+
+#[tauri::command]
+async fn export_prometheus(service: tauri::State<'_, YahooFinanceService>) -> Result<String, String> {
+    let entries = service.cache.snapshot_entries().await;
+    Ok(YahooFinanceService::render_prometheus(&service.telemetry.snapshot(), &entries))
+}
+
+// ---- ケリー基準で調整したエクイティカーブ ----
+const MAX_KELLY_LEVERAGE: f64 = 3.0;
+
+impl YahooFinanceService {
+    // 連続時間近似のケリー比率 f* = μ / σ²（日次リターンの標本平均・分散）
+    fn calculate_kelly_fraction(returns: &[f64]) -> f64 {
+        if returns.len() < 2 { return 0.0; }
+        let n = returns.len() as f64;
+        let mean = returns.iter().sum::<f64>() / n;
+        let var = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        if var > 0.0 { mean / var } else { 0.0 }
+    }
+
+    // 全期間で推定したケリー比率 × fraction_of_kelly を ±MAX_KELLY_LEVERAGE に制限して毎日リバランス
+    // 推定は同じ期間を使う（先読みを含む）ので、実運用の期待値ではなくサイズ感の比較用
+    fn calculate_kelly_equity_curve(returns: &[f64], fraction_of_kelly: f64) -> Result<Vec<f64>, AppError> {
+        if !fraction_of_kelly.is_finite() || fraction_of_kelly < 0.0 {
+            return Err(AppError::InvalidParameter("fraction_of_kelly must be non-negative".to_string()));
+        }
+        let leverage = (Self::calculate_kelly_fraction(returns) * fraction_of_kelly).clamp(-MAX_KELLY_LEVERAGE, MAX_KELLY_LEVERAGE);
+        let mut equity = 1.0_f64;
+        let mut curve = vec![equity];
+        for r in returns {
+            // 元本を割り込んだら破産として0で止める
+            equity = (equity * (1.0 + leverage * r)).max(0.0);
+            curve.push(equity);
+        }
+        Ok(curve)
+    }
+}
+
+#[tauri::command]
+async fn kelly_equity_curve(
+    symbol: String, range: String, interval: String, fraction_of_kelly: f64,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<Vec<f64>, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(_, analysis_result)| YahooFinanceService::calculate_kelly_equity_curve(&analysis_result.returns[1..], fraction_of_kelly));
+    match result {
+        Ok(curve) => Ok(curve),
+        Err(e) => {
+            error!("kelly_equity_curve error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- 支配的な周期の検出 ----
+impl YahooFinanceService {
+    // 標本自己相関 ρ(k) = Σ(x_t - m)(x_{t+k} - m) / Σ(x_t - m)²（添字 k-1 がラグ k）
+    fn calculate_autocorrelation(values: &[f64], max_lag: usize) -> Vec<f64> {
+        let n = values.len();
+        let mean = values.iter().sum::<f64>() / n as f64;
+        let denominator: f64 = values.iter().map(|v| (v - mean).powi(2)).sum();
+        (1..=max_lag.min(n.saturating_sub(1)))
+            .map(|lag| {
+                if denominator <= 0.0 { return 0.0; }
+                values.iter().zip(&values[lag..]).map(|(a, b)| (a - mean) * (b - mean)).sum::<f64>() / denominator
+            })
+            .collect()
+    }
+
+    // ラグ2以上の極大のうち、有意（> 2/√n）で最も高い正の自己相関を周期とみなす
+    fn detect_dominant_cycle(returns: &[f64], max_lag: usize) -> Result<Option<usize>, AppError> {
+        let max_lag = max_lag.min(MAX_AUTOCORR_LAGS);
+        if max_lag < 3 {
+            return Err(AppError::InvalidParameter("max_lag must be at least 3".to_string()));
+        }
+        if returns.len() < max_lag * 2 {
+            return Err(AppError::DataParsing("Insufficient data for cycle detection".to_string()));
+        }
+        let acf = Self::calculate_autocorrelation(returns, max_lag);
+        let threshold = 2.0 / (returns.len() as f64).sqrt();
+        let mut best: Option<(usize, f64)> = None;
+        for lag in 2..max_lag {
+            let value = acf[lag - 1];
+            let is_peak = value > acf[lag - 2] && value >= acf[lag];
+            if is_peak && value > threshold && best.is_none_or(|(_, v)| value > v) {
+                best = Some((lag, value));
+            }
+        }
+        Ok(best.map(|(lag, _)| lag))
+    }
+}
+
+#[tauri::command]
+async fn dominant_cycle(
+    symbol: String, range: String, interval: String, max_lag: usize,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<Option<usize>, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(_, analysis_result)| YahooFinanceService::detect_dominant_cycle(&analysis_result.returns[1..], max_lag));
+    match result {
+        Ok(cycle) => Ok(cycle),
+        Err(e) => {
+            error!("dominant_cycle error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- 銘柄ごとのキャッシュ更新情報 ----
+#[derive(Serialize, Debug)]
+struct SymbolMetadata {
+    last_cached_at: Option<String>,
+    last_data_date: Option<String>,
+    cached_ranges: Vec<String>,
+}
+
+impl YahooFinanceService {
+    // キャッシュ中のエントリだけを集計する（取得は行わない）
+    async fn collect_symbol_metadata(&self, symbol: &str) -> SymbolMetadata {
+        let resolved = self.resolve_symbol(symbol).await;
+        let mut last_cached_at: Option<chrono::DateTime<Utc>> = None;
+        let mut last_data_date: Option<String> = None;
+        let mut cached_ranges = Vec::new();
+        for (key, data) in self.cache.snapshot_entries().await {
+            let Some((key_symbol, range, _)) = Self::parse_cache_key(&key) else { continue };
+            if key_symbol != resolved { continue; }
+            last_cached_at = last_cached_at.max(Some(data.cached_at));
+            // ISO形式の日付は文字列比較で前後が決まる
+            last_data_date = last_data_date.max(data.data.dates.last().cloned());
+            cached_ranges.push(range.to_string());
+        }
+        cached_ranges.sort();
+        cached_ranges.dedup();
+        SymbolMetadata {
+            last_cached_at: last_cached_at.map(|t| t.to_rfc3339()),
+            last_data_date,
+            cached_ranges,
+        }
+    }
+}
+
+#[tauri::command]
+async fn symbol_metadata(symbol: String, service: tauri::State<'_, YahooFinanceService>) -> Result<SymbolMetadata, String> {
+    Ok(service.collect_symbol_metadata(&symbol).await)
+}
+
+// ---- ブロック・ブートストラップによる期末リターン分布 ----
+const DEFAULT_BOOTSTRAP_BLOCK: usize = 5;
+
+#[derive(Serialize, Debug)]
+struct TerminalReturnDistribution {
+    mean: f64,
+    median: f64,
+    p5: f64,
+    p95: f64,
+}
+
+impl YahooFinanceService {
+    // 重なりを許す連続ブロック（moving block）を繋いで horizon_days 日分の累積リターンを作る
+    // ブロック内の並びを保つので、短期の自己相関が残る
+    fn simulate_terminal_returns(returns: &[f64], horizon_days: usize, paths: usize, block_length: usize, seed: u64) -> Result<TerminalReturnDistribution, AppError> {
+        if paths == 0 || horizon_days == 0 || block_length == 0 {
+            return Err(AppError::InvalidParameter("horizon_days, paths and block_length must be positive".to_string()));
+        }
+        if returns.len() < block_length {
+            return Err(AppError::DataParsing("Insufficient data for block bootstrap".to_string()));
+        }
+        let paths = paths.min(MAX_SIMULATION_PATHS);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut terminal = Vec::with_capacity(paths);
+        for _ in 0..paths {
+            let mut growth = 1.0;
+            let mut remaining = horizon_days;
+            while remaining > 0 {
+                let start = rng.gen_range(0..=returns.len() - block_length);
+                let take = block_length.min(remaining);
+                for r in &returns[start..start + take] {
+                    growth *= 1.0 + r;
+                }
+                remaining -= take;
+            }
+            terminal.push(growth - 1.0);
+        }
+
+        terminal.sort_by(|a, b| a.total_cmp(b));
+        Ok(TerminalReturnDistribution {
+            mean: terminal.iter().sum::<f64>() / terminal.len() as f64,
+            median: Self::calculate_quantile(&terminal, 0.5),
+            p5: Self::calculate_quantile(&terminal, 0.05),
+            p95: Self::calculate_quantile(&terminal, 0.95),
+        })
+    }
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn simulate_terminal_return(
+    symbol: String, range: String, interval: String, horizon_days: usize, paths: usize,
+    block_length: Option<usize>, seed: Option<u64>,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<TerminalReturnDistribution, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(_, analysis_result)| YahooFinanceService::simulate_terminal_returns(
+            &analysis_result.returns[1..], horizon_days, paths,
+            block_length.unwrap_or(DEFAULT_BOOTSTRAP_BLOCK), seed.unwrap_or(DEFAULT_SIMULATION_SEED),
+        ));
+    match result {
+        Ok(distribution) => Ok(distribution),
+        Err(e) => {
+            error!("simulate_terminal_return error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- ドローダウン・アット・リスク ----
+impl YahooFinanceService {
+    // 日々のドローダウン（0以下）の経験分布で、(1 - confidence) の割合しか下回らない水準
+    fn calculate_drawdown_at_risk(prices: &[f64], confidence: f64) -> Result<f64, AppError> {
+        if !(confidence > 0.0 && confidence < 1.0) {
+            return Err(AppError::InvalidParameter("confidence must be in (0, 1)".to_string()));
+        }
+        if prices.len() < 2 {
+            return Err(AppError::DataParsing("Insufficient data for drawdown-at-risk".to_string()));
+        }
+        let mut drawdowns = Self::calculate_drawdown_series(prices);
+        drawdowns.sort_by(|a, b| a.total_cmp(b));
+        Ok(Self::calculate_quantile(&drawdowns, 1.0 - confidence))
+    }
+}
+
+#[tauri::command]
+async fn drawdown_at_risk(symbol: String, range: String, interval: String, confidence: f64, service: tauri::State<'_, YahooFinanceService>) -> Result<f64, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(series_payload, _)| YahooFinanceService::calculate_drawdown_at_risk(&series_payload.prices, confidence));
+    match result {
+        Ok(dar) => Ok(dar),
+        Err(e) => {
+            error!("drawdown_at_risk error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- リターンを加工した再解析 ----
+const RETURN_TRANSFORMS: &[&str] = &["demean", "standardize", "clip3sigma", "abs"];
+
+#[derive(Serialize, Debug)]
+struct TransformedAnalysis {
+    transform: String,
+    raw: AnalysisResult,
+    transformed: AnalysisResult,
+}
+
+impl YahooFinanceService {
+    // returns は先頭のプレースホルダーを含まない日次リターン
+    fn apply_return_transform(returns: &[f64], transform: &str) -> Result<Vec<f64>, AppError> {
+        if !RETURN_TRANSFORMS.contains(&transform) {
+            return Err(AppError::InvalidParameter(format!("Unknown transform: {} (expected one of {})", transform, RETURN_TRANSFORMS.join(", "))));
+        }
+        if returns.len() < 2 {
+            return Err(AppError::DataParsing("Insufficient data for transform".to_string()));
+        }
+        let n = returns.len() as f64;
+        let mean = returns.iter().sum::<f64>() / n;
+        let std = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+        Ok(match transform {
+            "demean" => returns.iter().map(|r| r - mean).collect(),
+            "standardize" => {
+                if std <= 0.0 {
+                    return Err(AppError::InvalidParameter("Cannot standardize returns with zero variance".to_string()));
+                }
+                returns.iter().map(|r| (r - mean) / std).collect()
+            }
+            "clip3sigma" => returns.iter().map(|r| r.clamp(mean - 3.0 * std, mean + 3.0 * std)).collect(),
+            _ => returns.iter().map(|r| r.abs()).collect(),
+        })
+    }
+
+    fn calculate_transformed_analysis(&self, prices: &[f64], raw: AnalysisResult, transform: &str) -> Result<TransformedAnalysis, AppError> {
+        let transformed_returns = Self::apply_return_transform(&raw.returns[1..], transform)?;
+        // 変換後の分布の統計量は先頭のプレースホルダー0.0を除いて計算し直す
+        let (mean, std) = return_moments(&transformed_returns);
+        let returns: Vec<f64> = std::iter::once(0.0).chain(transformed_returns).collect();
+        let mut transformed = self.analyze_with_returns(prices, returns)?;
+        let enabled = |name: &str| !transformed.disabled.iter().any(|d| d == name);
+        let (mean_enabled, std_enabled, sharpe_enabled) = (enabled("mean_return_daily"), enabled("std_return_daily"), enabled("sharpe_annual"));
+        if mean_enabled { transformed.mean_return_daily = mean; }
+        if std_enabled { transformed.std_return_daily = std; }
+        if sharpe_enabled { transformed.sharpe_annual = if std > 0.0 { (mean * 252.0_f64.sqrt()) / std } else { 0.0 }; }
+        Ok(TransformedAnalysis {
+            transform: transform.to_string(),
+            transformed,
+            raw,
+        })
+    }
+}
+
+#[tauri::command]
+async fn analyze_transformed(
+    symbol: String, range: String, interval: String, transform: String,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<TransformedAnalysis, String> {
+    let result = service.get_financial_data(&symbol, &range, &interval).await
+        .and_then(|(series_payload, analysis_result)| service.calculate_transformed_analysis(&series_payload.prices, analysis_result, &transform));
+    match result {
+        Ok(analysis) => Ok(analysis),
+        Err(e) => {
+            error!("analyze_transformed error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- 季節性（曜日・月）を除いたリターン ----
+impl YahooFinanceService {
+    // 曜日平均と月平均をそれぞれ全体平均との差として差し引く（全体のドリフトは残す）
+    // dates と returns は同じ長さで、returns[i] は dates[i] の日のリターン
+    fn calculate_deseasonalized_returns(dates: &[String], returns: &[f64]) -> Result<Vec<f64>, AppError> {
+        if dates.len() != returns.len() || returns.is_empty() {
+            return Err(AppError::InvalidParameter("dates and returns must be non-empty and equal length".to_string()));
+        }
+        let parsed = dates.iter().map(|d| Self::parse_iso_date(d)).collect::<Result<Vec<_>, _>>()?;
+        let overall = returns.iter().sum::<f64>() / returns.len() as f64;
+        let seasonal_means = |bucket: &dyn Fn(&chrono::NaiveDate) -> u32| -> HashMap<u32, f64> {
+            let mut sums: HashMap<u32, (f64, usize)> = HashMap::new();
+            for (date, &r) in parsed.iter().zip(returns) {
+                let entry = sums.entry(bucket(date)).or_insert((0.0, 0));
+                entry.0 += r;
+                entry.1 += 1;
+            }
+            sums.into_iter().map(|(k, (sum, count))| (k, sum / count as f64)).collect()
+        };
+        let weekday_means = seasonal_means(&|d| d.weekday().num_days_from_monday());
+        let month_means = seasonal_means(&|d| d.month());
+        Ok(parsed
+            .iter()
+            .zip(returns)
+            .map(|(date, &r)| {
+                r - (weekday_means[&date.weekday().num_days_from_monday()] - overall) - (month_means[&date.month()] - overall)
+            })
+            .collect())
+    }
+
+    fn calculate_deseasonalized_analysis(&self, series: &SeriesPayload, analysis: &AnalysisResult) -> Result<AnalysisResult, AppError> {
+        if series.dates.len() < 2 {
+            return Err(AppError::DataParsing("Insufficient data for deseasonalizing".to_string()));
+        }
+        let adjusted = Self::calculate_deseasonalized_returns(&series.dates[1..], &analysis.returns[1..])?;
+        let returns: Vec<f64> = std::iter::once(0.0).chain(adjusted).collect();
+        self.analyze_with_returns(&series.prices, returns)
+    }
+}
+
+// 日足で取得し、季節調整後のリターン（AnalysisResult.returns）とその指標を返す
+#[tauri::command]
+async fn deseasonalize(symbol: String, range: String, service: tauri::State<'_, YahooFinanceService>) -> Result<AnalysisResult, String> {
+    if !DAILY_CAPABLE_RANGES.contains(&range.as_str()) {
+        return Err(format!("季節調整に対応していない期間です: {}", range));
+    }
+    let result = service.get_financial_data(&symbol, &range, "1d").await
+        .and_then(|(series_payload, analysis_result)| service.calculate_deseasonalized_analysis(&series_payload, &analysis_result));
+    match result {
+        Ok(analysis) => Ok(analysis),
+        Err(e) => {
+            error!("deseasonalize error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- 複数期間のトレンド判定 ----
+const TREND_SIGNAL_RANGES: [&str; 3] = ["1mo", "6mo", "1y"];
+
+#[derive(Serialize, Debug)]
+struct TrendSignal {
+    short: String,  // 1mo
+    medium: String, // 6mo
+    long: String,   // 1y
+}
+
+impl YahooFinanceService {
+    // 終値が期間平均（期間全体のSMA）より上で、期間のモメンタムが正なら bullish。両方逆なら bearish
+    fn classify_trend(prices: &[f64]) -> &'static str {
+        let (Some(&first), Some(&last)) = (prices.first(), prices.last()) else { return "neutral" };
+        let sma = prices.iter().sum::<f64>() / prices.len() as f64;
+        let momentum = last / first - 1.0;
+        if last > sma && momentum > 0.0 {
+            "bullish"
+        } else if last < sma && momentum < 0.0 {
+            "bearish"
+        } else {
+            "neutral"
+        }
+    }
+
+    // 期間ごとに get_financial_data を通すので、キャッシュも期間ごとに効く
+    async fn calculate_trend_signal(&self, symbol: &str, interval: &str) -> Result<TrendSignal, AppError> {
+        let mut labels = Vec::with_capacity(TREND_SIGNAL_RANGES.len());
+        for range in TREND_SIGNAL_RANGES {
+            let (series, _) = self.get_financial_data(symbol, range, interval).await?;
+            labels.push(Self::classify_trend(&series.prices).to_string());
+        }
+        let mut labels = labels.into_iter();
+        Ok(TrendSignal {
+            short: labels.next().unwrap_or_default(),
+            medium: labels.next().unwrap_or_default(),
+            long: labels.next().unwrap_or_default(),
+        })
+    }
+}
+
+#[tauri::command]
+async fn trend_signal(symbol: String, interval: String, service: tauri::State<'_, YahooFinanceService>) -> Result<TrendSignal, String> {
+    match service.calculate_trend_signal(&symbol, &interval).await {
+        Ok(signal) => Ok(signal),
+        Err(e) => {
+            error!("trend_signal error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- セクターETFとの相関 ----
+const MAX_SECTOR_ETFS: usize = 15;
+
+impl YahooFinanceService {
+    // 各ETFと共通日付でのリターン相関を降順に並べる
+    fn calculate_sector_exposure(symbol: &SeriesPayload, etfs: &[(String, SeriesPayload)]) -> Result<Vec<(String, f64)>, AppError> {
+        let mut exposures = Vec::with_capacity(etfs.len());
+        for (name, etf) in etfs {
+            let (_, aligned) = Self::align_series(&[symbol, etf]);
+            if aligned[0].len() < 3 {
+                return Err(AppError::DataParsing(format!("Insufficient overlapping data with {}", name)));
+            }
+            let correlation = Self::calculate_correlation(
+                &Self::calculate_simple_returns(&aligned[0]),
+                &Self::calculate_simple_returns(&aligned[1]),
+            );
+            exposures.push((name.clone(), correlation));
+        }
+        exposures.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Ok(exposures)
+    }
+}
+
+#[tauri::command]
+async fn sector_exposure(
+    symbol: String, sector_etfs: Vec<String>, range: String, interval: String,
+    service: tauri::State<'_, YahooFinanceService>,
+) -> Result<Vec<(String, f64)>, String> {
+    if sector_etfs.is_empty() || sector_etfs.len() > MAX_SECTOR_ETFS {
+        return Err(format!("セクターETFは1〜{}件で指定してください", MAX_SECTOR_ETFS));
+    }
+    let result = async {
+        let (target, _) = service.get_financial_data(&symbol, &range, &interval).await?;
+        let mut etfs = Vec::with_capacity(sector_etfs.len());
+        for etf in &sector_etfs {
+            let (payload, _) = service.get_financial_data(etf, &range, &interval).await?;
+            etfs.push((etf.clone(), payload));
+        }
+        YahooFinanceService::calculate_sector_exposure(&target, &etfs)
+    }.await;
+    match result {
+        Ok(exposures) => Ok(exposures),
+        Err(e) => {
+            error!("sector_exposure error: {}", e);
+            Err(e.to_string())
+        }
+    }
+}
+
+// ---- 分析セッションの保存と再実行 ----
+const MAX_SESSION_SYMBOLS: usize = 20;
+const MAX_SESSION_NAME_CHARS: usize = 64;
+
+// 再実行時に適用する分析オプション。未対応のキーは保存時に弾く
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+struct SessionOptions {
+  // 指定があれば日次リターンを変換してから解析する（RETURN_TRANSFORMS のいずれか）
+  transform: Option<String>,
+}
+
+// データではなく分析条件だけを保存する
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct AnalysisSession {
+  name: String,
+  symbols: Vec<String>,
+  range: String,
+  interval: String,
+  #[serde(default)]
+  options: SessionOptions,
+}
+
+#[derive(Serialize, Debug)]
+struct SessionSymbolResult {
+  symbol: String,
+  series: Option<SeriesPayload>,
+  analysis: Option<AnalysisResult>,
+  error: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+struct SessionResult {
+  session: AnalysisSession,
+  results: Vec<SessionSymbolResult>,
+}
+
+fn validate_session_name(name: &str) -> Result<String, String> {
+  let name = name.trim();
+  if name.is_empty() || name.chars().count() > MAX_SESSION_NAME_CHARS {
+    return Err(format!("セッション名は1〜{}文字で指定してください", MAX_SESSION_NAME_CHARS));
+  }
+  if name.chars().any(|c| c.is_control()) {
+    return Err("セッション名に制御文字は使えません".into());
+  }
+  Ok(name.to_string())
+}
+
+impl YahooFinanceService {
+  // 銘柄ごとに取得・解析し、失敗した銘柄はエラーとして結果に含める
+  async fn replay_session(&self, session: AnalysisSession) -> SessionResult {
+    let mut results = Vec::with_capacity(session.symbols.len());
+    for symbol in &session.symbols {
+      let fetched = self.get_financial_data(symbol, &session.range, &session.interval).await
+        .and_then(|(series, analysis)| {
+          let analysis = self.apply_session_options(&series.prices, analysis, &session.options)?;
+          Ok((series, analysis))
+        });
+      let result = match fetched {
+        Ok((series, analysis)) => SessionSymbolResult { symbol: symbol.clone(), series: Some(series), analysis: Some(analysis), error: None },
+        Err(e) => {
+          warn!("Session {} failed for {}: {}", session.name, symbol, e);
+          SessionSymbolResult { symbol: symbol.clone(), series: None, analysis: None, error: Some(e.to_string()) }
+        }
+      };
+      results.push(result);
+    }
+    SessionResult { session, results }
+  }
+
+  fn apply_session_options(&self, prices: &[f64], analysis: AnalysisResult, options: &SessionOptions) -> Result<AnalysisResult, AppError> {
+    match &options.transform {
+      Some(transform) => Ok(self.calculate_transformed_analysis(prices, analysis, transform)?.transformed),
+      None => Ok(analysis),
+    }
+  }
+}
+
+// 保存済みセッションはストアの "analysis_sessions" に名前 → 条件のマップとして置く
+fn parse_sessions(value: Option<serde_json::Value>) -> Result<HashMap<String, AnalysisSession>, String> {
+  match value {
+    Some(value) => serde_json::from_value(value).map_err(|e| format!("セッションデシリアライズエラー: {}", e)),
+    None => Ok(HashMap::new()),
+  }
+}
+
+fn build_session(
+  name: &str, symbols: Vec<String>, range: String, interval: String, options: Option<serde_json::Value>,
+) -> Result<AnalysisSession, String> {
+  let name = validate_session_name(name)?;
+  let symbols: Vec<String> = symbols.iter().map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect();
+  if symbols.is_empty() || symbols.len() > MAX_SESSION_SYMBOLS {
+    return Err(format!("銘柄数は1〜{}件で指定してください", MAX_SESSION_SYMBOLS));
+  }
+  let options: SessionOptions = match options {
+    Some(serde_json::Value::Null) | None => SessionOptions::default(),
+    Some(value) => serde_json::from_value(value).map_err(|e| format!("セッションオプションが不正です: {}", e))?,
+  };
+  if let Some(transform) = &options.transform {
+    if !RETURN_TRANSFORMS.contains(&transform.as_str()) {
+      return Err(format!("未対応の変換です: {}（{} のいずれか）", transform, RETURN_TRANSFORMS.join(", ")));
+    }
+  }
+  Ok(AnalysisSession { name, symbols, range, interval, options })
+}
+
+// ストアの現在値にセッションを追加し、書き戻す値を返す
+fn insert_session(stored: Option<serde_json::Value>, session: AnalysisSession) -> Result<serde_json::Value, String> {
+  let mut sessions = parse_sessions(stored)?;
+  sessions.insert(session.name.clone(), session);
+  serde_json::to_value(&sessions).map_err(|e| format!("セッションシリアライズエラー: {}", e))
+}
+
+fn find_session(stored: Option<serde_json::Value>, name: &str) -> Result<AnalysisSession, String> {
+  let name = validate_session_name(name)?;
+  parse_sessions(stored)?
+    .remove(&name)
+    .ok_or_else(|| format!("セッション「{}」が見つかりません", name))
+}
+
+// セッションの保存先。テストではメモリ上のストアに差し替える
+trait SettingsStore {
+  fn get(&self, key: &str) -> Option<serde_json::Value>;
+  fn set(&self, key: &str, value: serde_json::Value);
+  async fn save(&self) -> Result<(), String>;
+}
+
+impl SettingsStore for tauri_plugin_store::Store<tauri::Wry> {
+  fn get(&self, key: &str) -> Option<serde_json::Value> {
+    tauri_plugin_store::Store::get(self, key)
+  }
+
+  fn set(&self, key: &str, value: serde_json::Value) {
+    tauri_plugin_store::Store::set(self, key, value)
+  }
+
+  async fn save(&self) -> Result<(), String> {
+    tauri_plugin_store::Store::save(self).await.map_err(|e| e.to_string())
+  }
+}
+
+async fn store_session<S: SettingsStore>(
+  store: &S, name: &str, symbols: Vec<String>, range: String, interval: String, options: Option<serde_json::Value>,
+) -> Result<String, String> {
+  let session = build_session(name, symbols, range, interval, options)?;
+  let name = session.name.clone();
+
+  let sessions_value = insert_session(store.get("analysis_sessions"), session)?;
+  store.set("analysis_sessions", sessions_value);
+  store.save().await
+    .map_err(|e| format!("セッション保存エラー: {}", e))?;
+
+  Ok(format!("セッション「{}」を保存しました", name))
+}
+
+async fn replay_stored_session<S: SettingsStore>(store: &S, name: &str, service: &YahooFinanceService) -> Result<SessionResult, String> {
+  let session = find_session(store.get("analysis_sessions"), name)?;
+  Ok(service.replay_session(session).await)
+}
+
+#[tauri::command]
+async fn save_session(
+  name: String, symbols: Vec<String>, range: String, interval: String, options: Option<serde_json::Value>,
+  app: tauri::AppHandle,
+) -> Result<String, String> {
+  let stores = app.store_collection();
+  let store = stores
+    .get("settings.json")
+    .ok_or("設定ストア取得失敗")?;
+
+  store_session(&store, &name, symbols, range, interval, options).await
+}
+
+#[tauri::command]
+async fn load_session(name: String, app: tauri::AppHandle, service: tauri::State<'_, YahooFinanceService>) -> Result<SessionResult, String> {
+  let stores = app.store_collection();
+  let store = stores
+    .get("settings.json")
+    .ok_or("設定ストア取得失敗")?;
+
+  replay_stored_session(&store, &name, &service).await
+}
+
+// 重複した関数を削除
+
+
+fn main() {
+    // ロギング初期化
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .init();
+    
+    info!("Starting Financial Dashboard Application");
+    
+    // セキュアキャッシュマネージャーを初期化 (最大100エントリ、50MB)
+    let cache_manager = Arc::new(SecureCacheManager::new(100, 50));
+    
+    // Yahoo Financeサービスを初期化
+    let yahoo_service = YahooFinanceService::new(cache_manager.clone());
+    
+    // バックグラウンドでキャッシュクリーンアップタスクを開始
+    let cleanup_cache = cache_manager.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(300)); // 5分間隔
+        loop {
+            interval.tick().await;
+            cleanup_cache.cleanup_expired().await;
+        }
+    });
+    
+    tauri::Builder::default()
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_store::Builder::default().build())
+        .manage(yahoo_service)
+        .setup(|app| {
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = load_symbol_aliases(&handle).await {
+                    warn!("Failed to load symbol aliases: {}", e);
+                }
+                match get_user_settings(handle.clone()).await {
+                    Ok(settings) => {
+                        let service = handle.state::<YahooFinanceService>();
+                        service.set_negative_cache_minutes(settings.negative_cache_minutes);
+                        configure_cache_events(&handle, &service, settings.cache_events_enabled);
+                        if let Err(e) = service.set_enabled_indicators(settings.enabled_indicators.as_deref()).await {
+                            warn!("Failed to apply indicator settings: {}", e);
+                        }
+                    }
+                    Err(e) => warn!("Failed to load user settings: {}", e),
+                }
+            });
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            fetch_yahoo, analyze_series, save_csv, save_yaml,
+            clear_cache, get_cache_info, remove_expired_cache,
+            get_user_settings, save_user_settings, list_indicators,
+            simulate_max_drawdown,
+            multi_factor_betas,
+            cache_size_audit,
+            rolling_var,
+            return_attribution,
+            optimize_sma_crossover,
+            weekly_return_stats,
+            get_telemetry, reset_telemetry,
+            volume_leads_price,
+            save_html_report,
+            burke_ratio,
+            save_alias, resolve_alias,
+            time_under_water,
+            beta_neutral_weights,
+            dca_backtest,
+            gap_analysis,
+            volatility_regime,
+            refresh_stale,
+            abs_return_autocorr,
+            detect_anomalies,
+            sharpe_newey_west,
+            range_volatility,
+            apply_stops,
+            correlation_stability,
+            calendar_gaps,
+            composite_score,
+            qq_normal,
+            net_strategy_return,
+            high_low_proximity,
+            rebalanced_portfolio,
+            return_entropy,
+            cointegration_test,
+            data_quality_report,
+            trend_fit,
+            export_influx_line,
+            asymmetric_beta,
+            rolling_return_distribution,
+            metrics_delta,
+            risk_contributions,
+            export_prometheus,
+            kelly_equity_curve,
+            dominant_cycle,
+            symbol_metadata,
+            simulate_terminal_return,
+            drawdown_at_risk,
+            analyze_transformed,
+            deseasonalize,
+            trend_signal,
+            sector_exposure,
+            save_session, load_session
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
+
+#[cfg(test)]
+mod main_test;
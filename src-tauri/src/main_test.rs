@@ -0,0 +1,1470 @@
+use super::*;
+
+fn synthetic_returns() -> Vec<f64> {
+    (0..250).map(|i| ((i * 37 % 11) as f64 - 5.0) * 0.004).collect()
+}
+
+#[test]
+fn simulate_max_drawdowns_is_deterministic_for_fixed_seed() {
+    let returns = synthetic_returns();
+    let first = YahooFinanceService::simulate_max_drawdowns(&returns, 500, 60, 7).unwrap();
+    let second = YahooFinanceService::simulate_max_drawdowns(&returns, 500, 60, 7).unwrap();
+
+    assert_eq!(first.mean, second.mean);
+    assert_eq!(first.p5, second.p5);
+    assert_eq!(first.p95, second.p95);
+    assert!(first.p5 <= first.mean && first.mean <= first.p95);
+    assert!(first.p95 <= 0.0);
+}
+
+fn series_fixture(symbol: &str, prices: &[f64]) -> SeriesPayload {
+    let start = chrono::NaiveDate::from_ymd_opt(2023, 1, 2).unwrap();
+    SeriesPayload {
+        symbol: symbol.to_string(),
+        dates: (0..prices.len()).map(|i| (start + Duration::days(i as i64)).to_string()).collect(),
+        prices: prices.to_vec(),
+        opens: vec![None; prices.len()],
+        highs: vec![None; prices.len()],
+        lows: vec![None; prices.len()],
+        volumes: vec![None; prices.len()],
+        dropped_nulls: 0,
+        cached: None,
+        cached_at: None,
+    }
+}
+
+fn prices_from_returns(returns: &[f64]) -> Vec<f64> {
+    let mut prices = vec![100.0];
+    for r in returns {
+        prices.push(prices.last().unwrap() * (1.0 + r));
+    }
+    prices
+}
+
+#[test]
+fn factor_betas_recover_constructed_coefficients() {
+    let n = 200;
+    let f1: Vec<f64> = (0..n).map(|i| if i % 2 == 0 { 0.01 } else { -0.01 }).collect();
+    let f2: Vec<f64> = (0..n).map(|i| if (i / 2) % 2 == 0 { 0.02 } else { -0.02 }).collect();
+    let target: Vec<f64> = (0..n).map(|i| 0.0005 + 1.5 * f1[i] - 0.7 * f2[i]).collect();
+
+    // 係数は取得後のシンボルではなく、指定したファクター名で引ける
+    let result = YahooFinanceService::calculate_factor_betas(
+        &series_fixture("TARGET", &prices_from_returns(&target)),
+        &[
+            ("market".to_string(), series_fixture("^GSPC", &prices_from_returns(&f1))),
+            ("r_squared".to_string(), series_fixture("SMB", &prices_from_returns(&f2))),
+        ],
+    ).unwrap();
+
+    assert_eq!(result.betas.len(), 2);
+    assert!((result.betas["market"] - 1.5).abs() < 1e-6);
+    assert!((result.betas["r_squared"] + 0.7).abs() < 1e-6);
+    assert!(result.r_squared > 0.999);
+}
+
+#[test]
+fn factor_validation_rejects_duplicates() {
+    let factors = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+    assert!(validate_factors(&factors(&["SPY", "IWM"])).is_ok());
+    assert!(validate_factors(&factors(&["SPY", " spy "])).unwrap_err().contains("重複"));
+    assert!(validate_factors(&[]).is_err());
+    assert!(validate_factors(&factors(&["A", "B", "C", "D", "E", "F"])).is_err());
+}
+
+fn cached_fixture(symbol: &str, prices: &[f64], ttl_minutes: i64) -> CachedData {
+    let service = YahooFinanceService::new(Arc::new(SecureCacheManager::new(10, 5)));
+    let analysis = service.analyze_financial_data(prices).unwrap();
+    CachedData::new(series_fixture(symbol, prices), analysis, ttl_minutes)
+}
+
+#[tokio::test]
+async fn size_audit_ratio_stays_within_reasonable_factor() {
+    let cache = SecureCacheManager::new(10, 5);
+    let prices: Vec<f64> = (0..250).map(|i| 100.0 + (i as f64 * 0.3).sin() * 5.0).collect();
+    cache.set("AAPL:1y:1d".to_string(), cached_fixture("AAPL", &prices, 15)).await.unwrap();
+
+    let audit = cache.size_audit().await.unwrap();
+    assert!(audit.estimated_bytes > 0);
+    assert!(audit.serialized_bytes > 0);
+    assert!(audit.ratio > 0.25 && audit.ratio < 10.0, "ratio {}", audit.ratio);
+}
+
+#[test]
+fn rolling_var_deepens_when_volatility_rises() {
+    let mut returns = vec![0.0];
+    returns.extend((1..200).map(|i| {
+        let scale = if i < 100 { 0.005 } else { 0.03 };
+        if i % 2 == 0 { scale } else { -scale }
+    }));
+
+    let var = YahooFinanceService::calculate_rolling_var(&returns, 20, 0.95);
+    assert!(var[..20].iter().all(|v| v.is_none()));
+
+    let mean = |slice: &[Option<f64>]| slice.iter().flatten().sum::<f64>() / slice.len() as f64;
+    let calm = mean(&var[20..100]);
+    let turbulent = mean(&var[120..]);
+    assert!(turbulent < calm, "calm {} turbulent {}", calm, turbulent);
+}
+
+#[test]
+fn return_attribution_reconciles_to_total_return() {
+    let prices: Vec<f64> = (0..730).map(|i| 100.0 * (1.0 + 0.0004 * i as f64) + (i as f64 * 0.2).sin()).collect();
+    let series = series_fixture("AAPL", &prices);
+
+    let yearly = YahooFinanceService::calculate_return_attribution(&series.dates, &series.prices, "year").unwrap();
+    assert_eq!(yearly.iter().map(|(p, _)| p.as_str()).collect::<Vec<_>>(), ["2023", "2024"]);
+
+    let total = prices.last().unwrap() / prices[0] - 1.0;
+    let compounded = yearly.iter().fold(1.0, |acc, (_, c)| acc * (1.0 + c)) - 1.0;
+    assert!((compounded - total).abs() < 1e-12);
+
+    assert!(YahooFinanceService::calculate_return_attribution(&series.dates, &series.prices, "decade").is_err());
+}
+
+#[test]
+fn sma_crossover_optimizer_beats_naive_default() {
+    let prices: Vec<f64> = (0..400)
+        .map(|i| 100.0 * 1.001_f64.powi(i) * (1.0 + 0.05 * (i as f64 / 15.0).sin()))
+        .collect();
+
+    let best = YahooFinanceService::optimize_sma_crossover(&prices, (2, 15), (10, 60)).unwrap();
+    assert!(best.best_fast < best.best_slow);
+
+    let naive = YahooFinanceService::calculate_annualized_sharpe(&YahooFinanceService::backtest_sma_crossover(&prices, 5, 20));
+    assert!(best.sharpe >= naive, "best {} naive {}", best.sharpe, naive);
+
+    assert!(YahooFinanceService::optimize_sma_crossover(&prices, (20, 30), (5, 10)).is_err());
+}
+
+#[test]
+fn weekly_returns_compound_to_daily_total() {
+    let prices: Vec<f64> = (0..120).map(|i| 100.0 + i as f64 * 0.1 + (i as f64 * 0.7).sin() * 2.0).collect();
+    let series = series_fixture("AAPL", &prices);
+
+    let weekly = YahooFinanceService::calculate_weekly_returns(&series.dates, &series.prices);
+    assert_eq!(weekly.len(), 18);
+
+    let daily_total = prices.last().unwrap() / prices[0] - 1.0;
+    let weekly_total = weekly.iter().fold(1.0, |acc, r| acc * (1.0 + r)) - 1.0;
+    assert!((weekly_total - daily_total).abs() < 1e-12);
+
+    let stats = YahooFinanceService::calculate_weekly_return_stats(&series.dates, &series.prices).unwrap();
+    assert!(((1.0 + stats.mean).powi(weekly.len() as i32) - 1.0 - daily_total).abs() < 0.05);
+    assert!(stats.worst_week <= stats.mean && stats.mean <= stats.best_week);
+}
+
+#[tokio::test]
+async fn telemetry_counts_cache_hits_and_resets() {
+    let cache = Arc::new(SecureCacheManager::new(10, 5));
+    let service = YahooFinanceService::new(cache.clone());
+    let prices: Vec<f64> = (0..30).map(|i| 100.0 + i as f64).collect();
+    cache.set("AAPL:1mo:1d".to_string(), cached_fixture("AAPL", &prices, 15)).await.unwrap();
+
+    service.get_financial_data("AAPL", "1mo", "1d").await.unwrap();
+    service.get_financial_data("AAPL", "1mo", "1d").await.unwrap();
+    service.telemetry.record_cache_miss();
+    service.telemetry.record_network_latency(std::time::Duration::from_millis(40));
+    service.telemetry.record_network_latency(std::time::Duration::from_millis(60));
+    service.telemetry.record_network_error("404");
+
+    let snapshot = service.telemetry.snapshot();
+    assert_eq!(snapshot.total_fetches, 2);
+    assert_eq!(snapshot.cache_hits, 2);
+    assert_eq!(snapshot.cache_misses, 1);
+    assert_eq!(snapshot.network_errors.get("404"), Some(&1));
+    assert!((snapshot.avg_fetch_latency_ms - 50.0).abs() < 1e-9);
+
+    service.telemetry.reset();
+    let snapshot = service.telemetry.snapshot();
+    assert_eq!(snapshot.total_fetches + snapshot.cache_hits + snapshot.cache_misses, 0);
+    assert!(snapshot.network_errors.is_empty());
+}
+
+#[test]
+fn volume_spikes_lead_price_moves_at_intended_lag() {
+    let lag = 2;
+    let n = 120;
+    let spike = |t: usize| t % 10 == 3;
+    let mut returns = vec![0.001; n - 1];
+    for t in 0..n {
+        if spike(t) && t + lag < n {
+            returns[t + lag - 1] = 0.04;
+        }
+    }
+    let mut series = series_fixture("AAPL", &prices_from_returns(&returns));
+    series.volumes = (0..n).map(|t| Some(if spike(t) { 5_000_000.0 } else { 1_000_000.0 })).collect();
+
+    let at_lag = YahooFinanceService::calculate_volume_lead_correlation(&series, lag).unwrap();
+    let same_day = YahooFinanceService::calculate_volume_lead_correlation(&series, 0).unwrap();
+    assert!(at_lag > 0.9, "corr at lag {}", at_lag);
+    assert!(same_day < at_lag);
+}
+
+#[test]
+fn html_report_embeds_table_and_chart() {
+    let prices: Vec<f64> = (0..60).map(|i| 100.0 + (i as f64 * 0.2).sin() * 3.0).collect();
+    let cached = cached_fixture("7203.T", &prices, 15);
+    let path = std::env::temp_dir().join(format!("report-{}.html", Uuid::new_v4()));
+    let path_str = path.to_string_lossy().to_string();
+
+    YahooFinanceService::write_html_report(&cached.data, &cached.analysis, "3mo", "1d", &path_str).unwrap();
+    let html = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    assert!(html.contains("7203.T"));
+    assert!(html.contains("<table>"));
+    assert!(html.contains("<img") && html.contains("data:image/svg+xml"));
+    assert!(html.contains("Yahoo Finance"));
+}
+
+// 100→peak→谷→回復 を線形補間で繋いだ価格系列
+fn path_through(points: &[f64], steps: usize) -> Vec<f64> {
+    let mut prices = vec![points[0]];
+    for w in points.windows(2) {
+        for s in 1..=steps {
+            prices.push(w[0] + (w[1] - w[0]) * s as f64 / steps as f64);
+        }
+    }
+    prices
+}
+
+#[test]
+fn burke_ratio_penalizes_deep_drawdown_more_than_shallow_ones() {
+    let deep = path_through(&[100.0, 110.0, 77.0, 110.0, 110.0, 110.0, 110.0, 110.0, 110.0, 110.0, 120.0], 10);
+    let shallow = path_through(&[100.0, 110.0, 104.5, 110.0, 104.5, 110.0, 104.5, 110.0, 104.5, 110.0, 120.0], 10);
+    assert_eq!(deep.len(), shallow.len());
+
+    assert_eq!(YahooFinanceService::find_drawdown_episodes(&deep).len(), 1);
+    assert_eq!(YahooFinanceService::find_drawdown_episodes(&shallow).len(), 4);
+
+    let deep_burke = YahooFinanceService::calculate_burke_ratio(&deep, 0.0).unwrap();
+    let shallow_burke = YahooFinanceService::calculate_burke_ratio(&shallow, 0.0).unwrap();
+    // sqrt(0.3²) = 0.3 > sqrt(4 × 0.05²) = 0.1
+    assert!((shallow_burke / deep_burke - 3.0).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn alias_fetch_hits_real_symbol_cache_key() {
+    let cache = Arc::new(SecureCacheManager::new(10, 5));
+    let service = YahooFinanceService::new(cache.clone());
+    let prices: Vec<f64> = (0..30).map(|i| 2500.0 + i as f64).collect();
+    cache.set("7203.T:1y:1d".to_string(), cached_fixture("7203.T", &prices, 15)).await.unwrap();
+
+    service.set_alias("Toyota", "7203.T").await;
+    assert_eq!(service.resolve_symbol("toyota").await, "7203.T");
+    assert_eq!(service.resolve_symbol("AAPL").await, "AAPL");
+
+    let (payload, _) = service.get_financial_data("Toyota", "1y", "1d").await.unwrap();
+    assert_eq!(payload.symbol, "7203.T");
+    assert_eq!(payload.cached, Some(true));
+    assert_eq!(service.telemetry.snapshot().cache_hits, 1);
+}
+
+#[test]
+fn time_under_water_measures_long_recovery() {
+    // 2日目の高値110 → 急落 → 19日目にようやく回復 → 20日目の新高値から再び下落中
+    let mut prices = vec![100.0, 105.0, 110.0, 100.0, 95.0];
+    prices.extend((96..=109).map(f64::from));
+    prices.extend([111.0, 112.0, 108.0, 109.0]);
+    let series = series_fixture("AAPL", &prices);
+
+    let episodes = YahooFinanceService::find_drawdown_episodes(&prices);
+    let result = YahooFinanceService::calculate_time_under_water(&series.dates, &series.prices).unwrap();
+    assert_eq!(episodes.len(), 2);
+    assert_eq!(result.longest_days, 17);
+    assert_eq!(result.current_days, 2);
+}
+
+#[test]
+fn beta_neutral_weights_cancel_combined_beta() {
+    let n = 200;
+    let bench: Vec<f64> = (0..n).map(|i| if i % 2 == 0 { 0.01 } else { -0.01 }).collect();
+    let idio: Vec<f64> = (0..n).map(|i| if (i / 2) % 2 == 0 { 0.005 } else { -0.005 }).collect();
+    let long: Vec<f64> = (0..n).map(|i| 1.5 * bench[i] + idio[i]).collect();
+    let short: Vec<f64> = (0..n).map(|i| 0.5 * bench[i] - idio[i]).collect();
+
+    let weights = YahooFinanceService::calculate_beta_neutral_weights(
+        &series_fixture("LONG", &prices_from_returns(&long)),
+        &series_fixture("SHORT", &prices_from_returns(&short)),
+        &series_fixture("BENCH", &prices_from_returns(&bench)),
+    ).unwrap();
+
+    assert!((weights.long_weight.abs() + weights.short_weight.abs() - 1.0).abs() < 1e-12);
+    assert!(weights.long_weight > 0.0 && weights.short_weight < 0.0);
+    let combined_beta = weights.long_weight * 1.5 + weights.short_weight * 0.5;
+    assert!(combined_beta.abs() < 1e-6, "combined beta {}", combined_beta);
+}
+
+#[test]
+fn dca_on_flat_prices_has_zero_irr() {
+    let series = series_fixture("FLAT", &[50.0; 180]);
+
+    let monthly = YahooFinanceService::calculate_dca_backtest(&series.dates, &series.prices, 100.0, "monthly").unwrap();
+    assert_eq!(monthly.total_invested, 600.0);
+    assert!((monthly.final_value - monthly.total_invested).abs() < 1e-9);
+    assert!(monthly.irr.abs() < 1e-6, "irr {}", monthly.irr);
+
+    let weekly = YahooFinanceService::calculate_dca_backtest(&series.dates, &series.prices, 100.0, "weekly").unwrap();
+    assert_eq!(weekly.total_invested, 2600.0);
+    assert!(YahooFinanceService::calculate_dca_backtest(&series.dates, &series.prices, 100.0, "daily").is_err());
+}
+
+#[test]
+fn gap_analysis_separates_overnight_gap() {
+    let closes = [100.0, 100.0, 110.0, 110.0, 110.0];
+    let mut series = series_fixture("AAPL", &closes);
+    // 3日目に +10% の窓開け、日中は横ばい
+    series.opens = vec![Some(100.0), Some(100.0), Some(110.0), Some(110.0), Some(110.0)];
+
+    let gaps = YahooFinanceService::calculate_gap_analysis(&series).unwrap();
+    assert_eq!(gaps.gap_series.len(), 4);
+    assert_eq!(gaps.gap_series[1].0, series.dates[2]);
+    assert!((gaps.gap_series[1].1 - 0.1).abs() < 1e-12);
+    assert!((gaps.mean_overnight - 0.025).abs() < 1e-12);
+    assert!(gaps.mean_intraday.abs() < 1e-12);
+
+    assert!(YahooFinanceService::calculate_gap_analysis(&series_fixture("AAPL", &closes)).is_err());
+}
+
+#[test]
+fn volatility_regime_flips_to_high_in_turbulent_section() {
+    let n = 240;
+    let mut returns = vec![0.0];
+    returns.extend((1..n).map(|i| {
+        let scale = if i < 160 { 0.004 } else { 0.03 };
+        if i % 2 == 0 { scale } else { -scale * 0.9 }
+    }));
+    let series = series_fixture("AAPL", &prices_from_returns(&returns[1..]));
+
+    let regimes = YahooFinanceService::classify_volatility_regimes(&series.dates, &returns, 20);
+    assert_eq!(regimes.len(), n - 20);
+    let label_on = |date: &str| regimes.iter().find(|(d, _)| d == date).map(|(_, l)| l.as_str());
+    assert_ne!(label_on(&series.dates[100]), Some("high"));
+    assert_eq!(label_on(&series.dates[220]), Some("high"));
+}
+
+fn chart_json(symbol: &str, closes: &[f64]) -> String {
+    let start = 1_700_000_000_i64;
+    serde_json::json!({
+        "chart": {
+            "result": [{
+                "meta": { "symbol": symbol, "timezone": "JST" },
+                "timestamp": (0..closes.len()).map(|i| start + i as i64 * 86_400).collect::<Vec<_>>(),
+                "indicators": { "quote": [{ "close": closes }] }
+            }],
+            "error": null
+        }
+    }).to_string()
+}
+
+// 1接続ごとに responder(リクエストパス) の (ステータス, ボディ) を返す簡易HTTPサーバー
+async fn spawn_mock_chart_server<F>(responder: F) -> (String, Arc<std::sync::atomic::AtomicUsize>)
+where
+    F: Fn(&str, usize) -> (u16, String) + Send + Sync + 'static,
+{
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}/v8/finance/chart", listener.local_addr().unwrap());
+    let hits = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = hits.clone();
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut socket, _)) = listener.accept().await else { break };
+            let mut buf = vec![0u8; 8192];
+            let n = socket.read(&mut buf).await.unwrap_or(0);
+            let request = String::from_utf8_lossy(&buf[..n]).to_string();
+            let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+            let attempt = counter.fetch_add(1, Ordering::SeqCst);
+            let (status, body) = responder(&path, attempt);
+            let response = format!(
+                "HTTP/1.1 {} OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status, body.len(), body
+            );
+            socket.write_all(response.as_bytes()).await.ok();
+            socket.shutdown().await.ok();
+        }
+    });
+    (base_url, hits)
+}
+
+#[tokio::test]
+async fn refresh_stale_refetches_only_old_entries() {
+    let (base_url, hits) = spawn_mock_chart_server(|path, _| {
+        let symbol = path.trim_start_matches("/v8/finance/chart/").split('?').next().unwrap_or("");
+        (200, chart_json(symbol, &[10.0, 11.0, 12.0, 13.0]))
+    }).await;
+    let cache = Arc::new(SecureCacheManager::new(10, 5));
+    let service = YahooFinanceService::new(cache.clone()).with_chart_base_url(&base_url);
+
+    let mut stale = cached_fixture("OLD", &[1.0, 2.0, 3.0], 120);
+    stale.cached_at = Utc::now() - Duration::minutes(90);
+    cache.set("OLD:1mo:1d".to_string(), stale).await.unwrap();
+    cache.set("NEW:1mo:1d".to_string(), cached_fixture("NEW", &[1.0, 2.0, 3.0], 120)).await.unwrap();
+
+    let summary = service.refresh_stale(60).await;
+    assert_eq!((summary.refreshed, summary.failed), (1, 0));
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+    let refreshed = cache.get("OLD:1mo:1d").await.unwrap();
+    assert_eq!(refreshed.data.prices, vec![10.0, 11.0, 12.0, 13.0]);
+    assert!(refreshed.cached_at > Utc::now() - Duration::minutes(1));
+    assert_eq!(cache.get("NEW:1mo:1d").await.unwrap().data.prices, vec![1.0, 2.0, 3.0]);
+}
+
+fn standard_normals(seed: u64, n: usize) -> Vec<f64> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..n)
+        .map(|_| {
+            let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+            let u2: f64 = rng.gen();
+            (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+        })
+        .collect()
+}
+
+#[test]
+fn abs_return_autocorr_detects_volatility_clustering() {
+    // GARCH(1,1): σ²_t = ω + α·r²_{t-1} + β·σ²_{t-1}
+    let shocks = standard_normals(11, 3000);
+    let mut variance: f64 = 1e-4;
+    let mut garch = Vec::with_capacity(shocks.len());
+    for z in &shocks {
+        let r = variance.sqrt() * z;
+        garch.push(r);
+        variance = 2e-6 + 0.15 * r * r + 0.83 * variance;
+    }
+    let iid: Vec<f64> = standard_normals(12, 3000).iter().map(|z| z * 0.01).collect();
+
+    let clustered = YahooFinanceService::calculate_abs_return_autocorr(&garch, 20);
+    let independent = YahooFinanceService::calculate_abs_return_autocorr(&iid, 20);
+    assert_eq!(clustered.len(), 20);
+    assert!(clustered[0].1 > 0.1, "lag1 {}", clustered[0].1);
+    assert!(clustered[0].1 > clustered[19].1);
+    assert!(independent.iter().all(|(_, c)| c.abs() < 0.08));
+}
+
+#[test]
+fn detect_anomalies_flags_flat_run_as_halt() {
+    let mut prices: Vec<f64> = (0..20).map(|i| 100.0 + i as f64 * 0.37).collect();
+    prices.extend([107.4; 10]);
+    prices.extend((0..10).map(|i| 108.0 + i as f64 * 0.41));
+    let mut series = series_fixture("HALT", &prices);
+    series.volumes = vec![Some(1000.0); prices.len()];
+    series.volumes[25] = Some(0.0);
+
+    let anomalies = YahooFinanceService::detect_anomalies(&series);
+    let flat: Vec<&Anomaly> = anomalies.iter().filter(|a| a.kind == "flat_run").collect();
+    assert_eq!(flat.len(), 1);
+    assert_eq!(flat[0].date, series.dates[20]);
+    assert!(flat[0].description.contains("10日"));
+    assert!(anomalies.iter().any(|a| a.kind == "zero_volume" && a.date == series.dates[25]));
+    assert!(anomalies.iter().all(|a| a.kind != "round_number_snap"));
+}
+
+#[test]
+fn newey_west_widens_sharpe_se_for_autocorrelated_returns() {
+    // AR(1)、φ = 0.5 の正の自己相関を持つリターン
+    let shocks = standard_normals(21, 2000);
+    let mut returns = Vec::with_capacity(shocks.len());
+    let mut previous = 0.0;
+    for z in shocks {
+        previous = 0.0005 + 0.5 * (previous - 0.0005) + 0.01 * z;
+        returns.push(previous);
+    }
+
+    let naive = YahooFinanceService::calculate_newey_west_sharpe(&returns, 0).unwrap();
+    let adjusted = YahooFinanceService::calculate_newey_west_sharpe(&returns, 10).unwrap();
+    assert_eq!(naive.sharpe, adjusted.sharpe);
+    assert!(adjusted.se > naive.se * 1.3, "naive {} adjusted {}", naive.se, adjusted.se);
+    assert!(adjusted.tstat.abs() < naive.tstat.abs());
+}
+
+#[tokio::test]
+async fn negative_cache_skips_network_for_known_bad_symbol() {
+    let (base_url, hits) = spawn_mock_chart_server(|path, _| {
+        if path.contains("/TYPO?") {
+            (404, r#"{"chart":{"result":null,"error":{"code":"Not Found","description":"No data found, symbol may be delisted"}}}"#.to_string())
+        } else {
+            (500, "upstream error".to_string())
+        }
+    }).await;
+    let service = YahooFinanceService::new(Arc::new(SecureCacheManager::new(10, 5))).with_chart_base_url(&base_url);
+
+    for _ in 0..2 {
+        let err = service.get_financial_data("TYPO", "1mo", "1d").await.unwrap_err();
+        assert!(matches!(err, AppError::SymbolNotFound(_)));
+    }
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+    // 一時的なエラーは記憶しない
+    service.get_financial_data("FLAKY", "1mo", "1d").await.unwrap_err();
+    service.get_financial_data("FLAKY", "1mo", "1d").await.unwrap_err();
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+}
+
+// 日中20ステップのランダムウォークから OHLC を作る（寄り付きは前日終値）
+fn ohlc_fixture(seed: u64, days: usize, step_vol: f64) -> SeriesPayload {
+    let shocks = standard_normals(seed, days * 20);
+    let mut price: f64 = 100.0;
+    let (mut opens, mut highs, mut lows, mut closes) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    for day in shocks.chunks(20) {
+        let open = price;
+        let (mut high, mut low) = (price, price);
+        for z in day {
+            price *= (step_vol * z).exp();
+            high = high.max(price);
+            low = low.min(price);
+        }
+        opens.push(Some(open));
+        highs.push(Some(high));
+        lows.push(Some(low));
+        closes.push(price);
+    }
+    let mut series = series_fixture("OHLC", &closes);
+    series.opens = opens;
+    series.highs = highs;
+    series.lows = lows;
+    series
+}
+
+#[test]
+fn range_volatility_is_comparable_to_close_to_close() {
+    let series = ohlc_fixture(31, 500, 0.003);
+    let returns = YahooFinanceService::calculate_simple_returns(&series.prices);
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let close_to_close = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / returns.len() as f64 * 252.0).sqrt();
+
+    let parkinson = YahooFinanceService::calculate_range_volatility(&series, "parkinson").unwrap();
+    let garman_klass = YahooFinanceService::calculate_range_volatility(&series, "garman_klass").unwrap();
+    assert!(parkinson > 0.0 && garman_klass > 0.0 && close_to_close > 0.0);
+    let ratio = parkinson / close_to_close;
+    assert!(ratio > 0.6 && ratio < 1.4, "parkinson/cc ratio {}", ratio);
+
+    assert!(YahooFinanceService::calculate_range_volatility(&series, "yang_zhang").is_err());
+    assert!(YahooFinanceService::calculate_range_volatility(&series_fixture("C", &series.prices), "parkinson").is_err());
+}
+
+#[test]
+fn stops_overlay_exits_at_take_profit_before_stop() {
+    let closes = [100.0, 103.0, 107.0, 108.0, 85.0, 80.0];
+    let mut series = series_fixture("AAPL", &closes);
+    series.highs = closes.iter().map(|c| Some(c + 1.5)).collect();
+    series.lows = closes.iter().map(|c| Some(c - 1.5)).collect();
+
+    // 3日目の高値108.5が利確ライン108に到達し、その後の急落に先行する
+    let result = YahooFinanceService::calculate_stops_overlay(&series, 0.1, 0.08).unwrap();
+    assert_eq!(result.exit_date.as_deref(), Some(series.dates[2].as_str()));
+    assert!((result.exit_return - 0.08).abs() < 1e-12);
+
+    let untouched = YahooFinanceService::calculate_stops_overlay(&series_fixture("AAPL", &closes[..4]), 0.1, 0.5).unwrap();
+    assert_eq!(untouched.exit_date, None);
+    assert!((untouched.exit_return - 0.08).abs() < 1e-12);
+}
+
+#[test]
+fn stops_overlay_fills_at_close_without_intraday_range() {
+    // 高安がない系列では、3日目の終値110で利確ラインを判定し終値で約定する
+    let series = series_fixture("AAPL", &[100.0, 104.0, 110.0, 90.0]);
+    let result = YahooFinanceService::calculate_stops_overlay(&series, 0.05, 0.08).unwrap();
+    assert_eq!(result.exit_date.as_deref(), Some(series.dates[2].as_str()));
+    assert!((result.exit_return - 0.10).abs() < 1e-12);
+
+    let stopped = YahooFinanceService::calculate_stops_overlay(&series_fixture("AAPL", &[100.0, 98.0, 92.0]), 0.05, 0.5).unwrap();
+    assert_eq!(stopped.exit_date.as_deref(), Some("2023-01-04"));
+    assert!((stopped.exit_return + 0.08).abs() < 1e-12);
+}
+
+#[test]
+fn stops_overlay_gap_down_fills_at_open() {
+    let closes = [100.0, 99.0, 85.0];
+    let mut series = series_fixture("AAPL", &closes);
+    series.opens = vec![Some(100.0), Some(100.0), Some(88.0)];
+    series.highs = vec![Some(100.5), Some(100.5), Some(89.0)];
+    series.lows = vec![Some(99.5), Some(98.5), Some(84.0)];
+
+    // 損切りライン95を下回って寄り付いたため、95ではなく始値88で約定する
+    let result = YahooFinanceService::calculate_stops_overlay(&series, 0.05, 0.2).unwrap();
+    assert_eq!(result.exit_date.as_deref(), Some(series.dates[2].as_str()));
+    assert!((result.exit_return + 0.12).abs() < 1e-12);
+}
+
+#[test]
+fn correlation_stability_reports_wide_swings() {
+    // 60日ごとに正相関と逆相関が入れ替わるペア
+    let base = standard_normals(41, 360);
+    let noise = standard_normals(42, 360);
+    let a: Vec<f64> = base.iter().map(|z| z * 0.01).collect();
+    let b: Vec<f64> = (0..360)
+        .map(|i| {
+            let sign = if (i / 60) % 2 == 0 { 1.0 } else { -1.0 };
+            sign * base[i] * 0.01 + noise[i] * 0.003
+        })
+        .collect();
+
+    let stability = YahooFinanceService::calculate_correlation_stability(
+        &series_fixture("A", &prices_from_returns(&a)),
+        &series_fixture("B", &prices_from_returns(&b)),
+        20,
+    ).unwrap();
+    assert!(stability.std > 0.5, "std {}", stability.std);
+    assert!(stability.max > 0.8 && stability.min < -0.8);
+    assert!(stability.mean.abs() < 0.3);
+}
+
+// 平日のみの日足。skip に含まれる営業日インデックスは欠落させる
+fn business_day_fixture(symbol: &str, days: usize, skip: std::ops::Range<usize>) -> SeriesPayload {
+    let mut date = chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    let mut dates = Vec::new();
+    let mut index = 0;
+    while index < days {
+        if date.weekday().number_from_monday() <= 5 {
+            if !skip.contains(&index) {
+                dates.push(date.to_string());
+            }
+            index += 1;
+        }
+        date += Duration::days(1);
+    }
+    let prices: Vec<f64> = (0..dates.len()).map(|i| 100.0 + i as f64 * 0.1).collect();
+    let mut series = series_fixture(symbol, &prices);
+    series.dates = dates;
+    series
+}
+
+#[test]
+fn calendar_gaps_reports_two_week_hole_only() {
+    let series = business_day_fixture("AAPL", 60, 20..30);
+    let gaps = YahooFinanceService::find_calendar_gaps(&series, YahooFinanceService::expected_max_gap_days("1d")).unwrap();
+
+    assert_eq!(gaps.len(), 1);
+    let (from, to, days) = &gaps[0];
+    assert_eq!((from.as_str(), to.as_str()), ("2024-01-26", "2024-02-12"));
+    assert_eq!(*days, 17);
+}
+
+#[test]
+fn composite_score_ranks_by_momentum() {
+    let wiggle: Vec<f64> = (0..300).map(|i| if i % 2 == 0 { 0.01 } else { -0.01 }).collect();
+    let with_drift = |drift: f64| prices_from_returns(&wiggle.iter().map(|w| w + drift).collect::<Vec<_>>());
+
+    let scores = YahooFinanceService::calculate_composite_scores(&[
+        series_fixture("FLAT", &with_drift(0.0)),
+        series_fixture("UP", &with_drift(0.002)),
+        series_fixture("DOWN", &with_drift(-0.002)),
+    ]).unwrap();
+
+    let order: Vec<&str> = scores.iter().map(|(s, _)| s.as_str()).collect();
+    assert_eq!(order, ["UP", "FLAT", "DOWN"]);
+    assert!(scores[0].1 > 0.0 && scores[2].1 < 0.0);
+}
+
+fn capturing_notifier(debounce_ms: u64) -> (CacheNotifier, Arc<std::sync::Mutex<Vec<CacheChangedEvent>>>) {
+    let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sink = events.clone();
+    let listener: CacheListener = Arc::new(move |event| sink.lock().unwrap().push(event));
+    (CacheNotifier::new(listener, std::time::Duration::from_millis(debounce_ms)), events)
+}
+
+#[tokio::test]
+async fn cache_notifier_emits_set_and_remove_events() {
+    let cache = SecureCacheManager::new(10, 5);
+    let (notifier, events) = capturing_notifier(0);
+    cache.set_notifier(Some(notifier));
+
+    cache.set("AAPL:1y:1d".to_string(), cached_fixture("AAPL", &[1.0, 2.0, 3.0], 15)).await.unwrap();
+    cache.remove("AAPL:1y:1d").await;
+    cache.remove("MISSING:1y:1d").await;
+
+    let events = events.lock().unwrap().clone();
+    assert_eq!(events, vec![
+        CacheChangedEvent { action: "set".to_string(), key: Some("AAPL:1y:1d".to_string()), entry_count: 1 },
+        CacheChangedEvent { action: "remove".to_string(), key: Some("AAPL:1y:1d".to_string()), entry_count: 0 },
+    ]);
+}
+
+#[tokio::test]
+async fn cache_notifier_emits_evict_for_capacity_overflow() {
+    let cache = SecureCacheManager::new(2, 5);
+    let (notifier, events) = capturing_notifier(0);
+    cache.set_notifier(Some(notifier));
+
+    for symbol in ["AAPL", "MSFT", "AAPL", "GOOG"] {
+        cache.set(format!("{}:1y:1d", symbol), cached_fixture(symbol, &[1.0, 2.0, 3.0], 15)).await.unwrap();
+    }
+
+    // 同じキーの上書きは追い出しではないため、evict は GOOG で溢れた MSFT の1件だけ
+    let events = events.lock().unwrap().clone();
+    let evicted: Vec<_> = events.iter().filter(|e| e.action == "evict").collect();
+    assert_eq!(evicted, vec![
+        &CacheChangedEvent { action: "evict".to_string(), key: Some("MSFT:1y:1d".to_string()), entry_count: 2 },
+    ]);
+    assert_eq!(events.last().unwrap().key.as_deref(), Some("GOOG:1y:1d"));
+    assert_eq!(events.iter().filter(|e| e.action == "set").count(), 4);
+}
+
+#[tokio::test]
+async fn cache_notifier_debounces_bursts() {
+    let cache = SecureCacheManager::new(10, 5);
+    let (notifier, events) = capturing_notifier(50);
+    cache.set_notifier(Some(notifier));
+
+    for i in 0..5 {
+        cache.set(format!("SYM{}:1y:1d", i), cached_fixture("SYM", &[1.0, 2.0, 3.0], 15)).await.unwrap();
+    }
+    tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+
+    let events = events.lock().unwrap().clone();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].key.as_deref(), Some("SYM4:1y:1d"));
+    assert_eq!(events[0].entry_count, 5);
+}
+
+#[test]
+fn qq_normal_points_follow_identity_for_normal_returns() {
+    let returns: Vec<f64> = standard_normals(17, 2000).into_iter().map(|z| 0.0005 + 0.01 * z).collect();
+    let points = YahooFinanceService::calculate_qq_normal(&returns).unwrap();
+
+    assert_eq!(points.len(), returns.len());
+    assert!(points.windows(2).all(|w| w[0].0 < w[1].0 && w[0].1 <= w[1].1));
+    assert!((YahooFinanceService::inverse_normal_cdf(0.975) - 1.959964).abs() < 1e-6);
+    // 極端な裾は標本誤差が大きいので中央95%で確認
+    let lo = points.len() / 40;
+    for &(theoretical, empirical) in &points[lo..points.len() - lo] {
+        assert!((theoretical - empirical).abs() < 0.15, "{} vs {}", theoretical, empirical);
+    }
+}
+
+#[test]
+fn net_strategy_return_penalizes_high_turnover() {
+    // 同じ個数の正リターン期間に乗るが、高回転側は毎期ポジションを入れ替える
+    let returns: Vec<f64> = (0..20).map(|i| if i % 2 == 0 { 0.01 } else { 0.0 }).collect();
+    let high_turnover: Vec<f64> = (0..20).map(|i| if i % 2 == 0 { 1.0 } else { 0.0 }).collect();
+    let low_turnover: Vec<f64> = vec![1.0; 20];
+
+    let high = YahooFinanceService::calculate_net_strategy_return(&high_turnover, &returns, 10.0).unwrap();
+    let low = YahooFinanceService::calculate_net_strategy_return(&low_turnover, &returns, 10.0).unwrap();
+
+    assert!((high.gross - low.gross).abs() < 1e-12);
+    assert!((low.total_cost - 0.001).abs() < 1e-12);
+    assert!((high.total_cost - 0.02).abs() < 1e-12);
+    assert!(high.net < low.net);
+    assert!(low.net < low.gross);
+    assert!(YahooFinanceService::calculate_net_strategy_return(&[1.0], &[0.01, 0.02], 5.0).is_err());
+}
+
+#[test]
+fn high_low_proximity_at_all_time_high() {
+    // 500日間の上昇トレンド（最終日が最高値）
+    let prices: Vec<f64> = (0..500).map(|i| 100.0 + i as f64 * 0.5).collect();
+    let series = series_fixture("UP", &prices);
+    let proximity = YahooFinanceService::calculate_high_low_proximity(&series.dates, &series.prices).unwrap();
+
+    assert!(proximity.pct_from_52w_high.abs() < 1e-12);
+    assert!(!proximity.partial_window);
+    // 安値は365日前の終値（全期間の最安値ではない）
+    assert!((proximity.low - (100.0 + 134.0 * 0.5)).abs() < 1e-12);
+    assert!(proximity.pct_from_52w_low > 0.0);
+
+    let short = series_fixture("SHORT", &prices[..100]);
+    let partial = YahooFinanceService::calculate_high_low_proximity(&short.dates, &short.prices).unwrap();
+    assert!(partial.partial_window);
+    assert!((partial.low - 100.0).abs() < 1e-12);
+}
+
+struct LastPriceIndicator;
+struct RangeIndicator;
+
+impl Indicator for LastPriceIndicator {
+    fn name(&self) -> &str { "last_price" }
+    fn compute(&self, ctx: &SeriesContext) -> IndicatorOutput {
+        IndicatorOutput::Scalar(*ctx.prices.last().unwrap())
+    }
+}
+
+impl Indicator for RangeIndicator {
+    fn name(&self) -> &str { "range" }
+    fn compute(&self, ctx: &SeriesContext) -> IndicatorOutput {
+        let max = ctx.prices.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let min = ctx.prices.iter().cloned().fold(f64::INFINITY, f64::min);
+        IndicatorOutput::Scalar(max - min)
+    }
+}
+
+#[test]
+fn custom_pipeline_outputs_only_its_indicators() {
+    let prices = vec![100.0, 104.0, 98.0, 101.0];
+    let service = YahooFinanceService::new(Arc::new(SecureCacheManager::new(10, 5)))
+        .with_pipeline(vec![Box::new(LastPriceIndicator), Box::new(RangeIndicator)]);
+    let analysis = service.analyze_financial_data(&prices).unwrap();
+
+    let names: Vec<&str> = analysis.extra.keys().map(String::as_str).collect();
+    assert_eq!(names, vec!["last_price", "range"]);
+    assert_eq!(analysis.extra["last_price"], IndicatorOutput::Scalar(101.0));
+    assert_eq!(analysis.extra["range"], IndicatorOutput::Scalar(6.0));
+    // 既定指標は無効化され、日次リターンは常に計算される
+    assert_eq!(analysis.sharpe_annual, 0.0);
+    assert!(analysis.sma5.iter().all(Option::is_none));
+    assert_eq!(analysis.returns.len(), prices.len());
+
+    // 既定パイプラインの extra は rolling_sortino60 のみ
+    let default = YahooFinanceService::new(Arc::new(SecureCacheManager::new(10, 5))).analyze_financial_data(&prices).unwrap();
+    assert_eq!(default.extra.keys().collect::<Vec<_>>(), vec!["rolling_sortino60"]);
+    assert!(default.std_return_daily > 0.0);
+    assert!(default.disabled.is_empty());
+    assert_eq!(analysis.disabled, vec!["mean_return_daily", "std_return_daily", "sharpe_annual", "sma5", "sma20"]);
+}
+
+#[tokio::test]
+async fn enabled_indicators_setting_disables_the_rest() {
+    let cache = Arc::new(SecureCacheManager::new(10, 5));
+    let service = YahooFinanceService::new(cache.clone());
+    let prices: Vec<f64> = (0..80).map(|i| 100.0 + (i as f64 * 0.4).sin() * 3.0).collect();
+    cache.set("AAPL:1y:1d".to_string(), cached_fixture("AAPL", &prices, 15)).await.unwrap();
+
+    let enabled = vec!["mean_return_daily".to_string(), "sma5".to_string()];
+    service.set_enabled_indicators(Some(&enabled)).await.unwrap();
+    // 旧構成の解析結果を返さないようキャッシュを破棄する
+    assert_eq!(cache.get_stats().await.entry_count, 0);
+
+    let analysis = service.analyze_financial_data(&prices).unwrap();
+    assert_eq!(analysis.disabled, vec!["std_return_daily", "sharpe_annual", "sma20", "rolling_sortino60"]);
+    assert!(analysis.extra.is_empty());
+    assert!(analysis.mean_return_daily != 0.0);
+    assert!(analysis.sma5.iter().any(Option::is_some));
+
+    let unknown = vec!["sma200".to_string()];
+    assert!(matches!(service.set_enabled_indicators(Some(&unknown)).await, Err(AppError::InvalidParameter(_))));
+    service.set_enabled_indicators(None).await.unwrap();
+    assert!(service.analyze_financial_data(&prices).unwrap().disabled.is_empty());
+}
+
+#[test]
+fn rebalancing_anti_correlated_assets_lowers_volatility() {
+    // 逆相関の2資産。片方が上昇トレンドのため、放置すると配分が偏り分散効果が薄れる
+    let shocks = standard_normals(23, 500);
+    let returns_a: Vec<f64> = shocks.iter().map(|z| 0.002 + 0.01 * z).collect();
+    let returns_b: Vec<f64> = shocks.iter().map(|z| -0.001 - 0.01 * z).collect();
+    let a = series_fixture("A", &prices_from_returns(&returns_a));
+    let b = series_fixture("B", &prices_from_returns(&returns_b));
+    let service = YahooFinanceService::new(Arc::new(SecureCacheManager::new(10, 5)));
+
+    let portfolio = service.calculate_rebalanced_portfolio(&a, &b, 0.5, "weekly").unwrap();
+    assert_eq!(portfolio.dates.len(), a.prices.len());
+    assert!(portfolio.rebalanced.std_return_daily < portfolio.buy_and_hold.std_return_daily);
+    assert!(service.calculate_rebalanced_portfolio(&a, &b, 0.5, "daily").is_err());
+}
+
+#[test]
+fn return_entropy_orders_concentrated_below_spread() {
+    // ほぼ全てが同じ値で、外れ値が1つだけ
+    let mut concentrated = vec![0.001; 499];
+    concentrated.push(0.05);
+    let spread: Vec<f64> = (0..500).map(|i| -0.05 + 0.1 * i as f64 / 499.0).collect();
+
+    let low = YahooFinanceService::calculate_return_entropy(&concentrated, 20).unwrap();
+    let high = YahooFinanceService::calculate_return_entropy(&spread, 20).unwrap();
+
+    assert!(low < 0.05, "low {}", low);
+    assert!(high > 0.99, "high {}", high);
+    assert!(low < high);
+    assert!(YahooFinanceService::calculate_return_entropy(&spread, 1).is_err());
+}
+
+fn random_walk(seed: u64, n: usize, step: f64) -> Vec<f64> {
+    let mut level = 0.0;
+    standard_normals(seed, n).into_iter().map(|z| { level += step * z; level }).collect()
+}
+
+#[test]
+fn cointegration_test_separates_cointegrated_and_independent_walks() {
+    let walk_b = random_walk(31, 500, 0.01);
+    let noise = standard_normals(32, 500);
+    // log A = 0.5 + 1.5·log B + 定常ノイズ
+    let log_a: Vec<f64> = walk_b.iter().zip(&noise).map(|(b, e)| 0.5 + 1.5 * b + 0.005 * e).collect();
+    let to_series = |symbol: &str, logs: &[f64]| series_fixture(symbol, &logs.iter().map(|l| 100.0 * l.exp()).collect::<Vec<_>>());
+
+    let result = YahooFinanceService::calculate_cointegration(&to_series("A", &log_a), &to_series("B", &walk_b)).unwrap();
+    assert!(result.is_cointegrated, "adf {}", result.adf_stat);
+    assert!((result.beta - 1.5).abs() < 0.05);
+
+    let independent = random_walk(33, 500, 0.01);
+    let result = YahooFinanceService::calculate_cointegration(&to_series("C", &independent), &to_series("B", &walk_b)).unwrap();
+    assert!(!result.is_cointegrated, "adf {}", result.adf_stat);
+}
+
+#[test]
+fn data_quality_report_scores_clean_above_gappy() {
+    let clean = business_day_fixture("CLEAN", 252, 0..0);
+    let clean_report = YahooFinanceService::calculate_data_quality(&clean, "1y", "1d").unwrap();
+    assert_eq!(clean_report.actual_points, 252);
+    assert_eq!(clean_report.gaps, 0);
+    assert!(clean_report.expected_points.abs_diff(252) <= 3, "expected {}", clean_report.expected_points);
+    assert!(clean_report.quality_score > 0.98, "score {}", clean_report.quality_score);
+
+    let mut gappy = business_day_fixture("GAPPY", 252, 100..160);
+    gappy.dropped_nulls = 15;
+    let gappy_report = YahooFinanceService::calculate_data_quality(&gappy, "1y", "1d").unwrap();
+    assert_eq!(gappy_report.gaps, 1);
+    assert_eq!(gappy_report.dropped_nulls, 15);
+    assert!(gappy_report.quality_score < 0.8, "score {}", gappy_report.quality_score);
+}
+
+async fn cache_workload(cache: Arc<SecureCacheManager>, workers: usize, ops: usize) {
+    let data = cached_fixture("SYM", &[1.0, 2.0, 3.0], 15);
+    let tasks: Vec<_> = (0..workers)
+        .map(|w| {
+            let cache = cache.clone();
+            let data = data.clone();
+            tokio::spawn(async move {
+                for i in 0..ops {
+                    let key = format!("W{}S{}:1y:1d", w, i % 25);
+                    cache.set(key.clone(), data.clone()).await.unwrap();
+                    // 書き込みの間に読み込みを挟み、ロックを長めに奪い合わせる
+                    for _ in 0..4 {
+                        assert!(cache.get(&key).await.is_some());
+                    }
+                }
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn sharded_cache_aggregates_stats_under_concurrency() {
+    let single = Arc::new(SecureCacheManager::with_shards(1000, 50, 1));
+    let sharded = Arc::new(SecureCacheManager::with_shards(1000, 50, 8));
+    cache_workload(single.clone(), 8, 500).await;
+    cache_workload(sharded.clone(), 8, 500).await;
+
+    // 8ワーカー × 25キー。シャードをまたいでも合計は一致する
+    let single_stats = single.get_stats().await;
+    let sharded_stats = sharded.get_stats().await;
+    assert_eq!(sharded_stats.entry_count, 200);
+    assert_eq!(sharded_stats.entry_count, single_stats.entry_count);
+    assert_eq!(sharded_stats.size_bytes, single_stats.size_bytes);
+    assert_eq!(sharded.keys_older_than(Duration::minutes(-1)).await.len(), 200);
+
+    assert_eq!(sharded.clear().await, 200);
+    let cleared = sharded.get_stats().await;
+    assert_eq!((cleared.entry_count, cleared.size_bytes), (0, 0));
+}
+
+#[tokio::test]
+async fn sharded_cache_writes_do_not_wait_on_other_shards() {
+    let data = cached_fixture("SYM", &[1.0, 2.0, 3.0], 15);
+    let sharded = SecureCacheManager::with_shards(1000, 50, 8);
+    let busy = "BUSY:1y:1d";
+    let (other_shard, same_shard): (Vec<String>, Vec<String>) = (0..100)
+        .map(|i| format!("K{}:1y:1d", i))
+        .partition(|k| !std::ptr::eq(sharded.shard(k), sharded.shard(busy)));
+    let wait = std::time::Duration::from_millis(50);
+
+    // 1シャードの書き込みロックを握ったまま、他シャードへの書き込みは完了する
+    {
+        let _held = sharded.shard(busy).store.write().await;
+        let free = tokio::time::timeout(std::time::Duration::from_secs(5), sharded.set(other_shard[0].clone(), data.clone())).await;
+        assert!(free.unwrap().is_ok());
+        assert!(tokio::time::timeout(wait, sharded.set(same_shard[0].clone(), data.clone())).await.is_err());
+    }
+    assert!(sharded.get(&other_shard[0]).await.is_some());
+
+    // 単一シャードではどのキーも同じロックを待つ
+    let single = SecureCacheManager::with_shards(1000, 50, 1);
+    let _held = single.shard(busy).store.write().await;
+    assert!(tokio::time::timeout(wait, single.set(other_shard[0].clone(), data.clone())).await.is_err());
+}
+
+// 計測用。`cargo test -- --ignored` で実行する
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+#[ignore]
+async fn sharded_cache_throughput_benchmark() {
+    let elapsed = |shards: usize| async move {
+        let started = std::time::Instant::now();
+        cache_workload(Arc::new(SecureCacheManager::with_shards(1000, 50, shards)), 8, 2000).await;
+        started.elapsed()
+    };
+    let single_elapsed = elapsed(1).await;
+    let sharded_elapsed = elapsed(8).await;
+    assert!(sharded_elapsed < single_elapsed, "single shard: {:?}, 8 shards: {:?}", single_elapsed, sharded_elapsed);
+}
+
+#[tokio::test]
+async fn full_shard_evicts_before_global_capacity() {
+    // 全体4件・2シャードなので各シャードの上限は2件
+    let cache = SecureCacheManager::with_shards(4, 5, 2);
+    let first = "K0:1y:1d".to_string();
+    let same_shard: Vec<String> = std::iter::once(first.clone())
+        .chain((1..100).map(|i| format!("K{}:1y:1d", i)).filter(|k| std::ptr::eq(cache.shard(k), cache.shard(&first))))
+        .take(3)
+        .collect();
+    for key in &same_shard {
+        cache.set(key.clone(), cached_fixture("K", &[1.0, 2.0, 3.0], 15)).await.unwrap();
+    }
+
+    assert_eq!(cache.get_stats().await.entry_count, 2);
+    assert!(cache.get(&same_shard[0]).await.is_none());
+    assert!(cache.get(&same_shard[2]).await.is_some());
+}
+
+#[test]
+fn trend_fit_recovers_exponential_growth() {
+    let slope = 0.0004;
+    let prices: Vec<f64> = (0..300).map(|i| 50.0 * (slope * i as f64).exp()).collect();
+    let series = series_fixture("EXP", &prices);
+    let fit = YahooFinanceService::calculate_trend_fit(&series.dates, &series.prices).unwrap();
+
+    assert!((fit.slope_per_day - slope).abs() < 1e-10);
+    assert!((fit.annualized_trend - ((slope * 365.0).exp() - 1.0)).abs() < 1e-8);
+    assert!(fit.r_squared > 0.999999);
+}
+
+#[test]
+fn rolling_sortino_matches_scalar_over_full_window() {
+    let daily: Vec<f64> = standard_normals(41, 120).into_iter().map(|z| 0.0004 + 0.012 * z).collect();
+    // analysis.returns と同じく先頭に0.0のプレースホルダー
+    let returns: Vec<f64> = std::iter::once(0.0).chain(daily.iter().copied()).collect();
+
+    let rolling = YahooFinanceService::calculate_rolling_sortino(&returns, daily.len(), 0.0);
+    let scalar = YahooFinanceService::calculate_sortino(&daily, 0.0).unwrap();
+    assert!(rolling[..daily.len()].iter().all(Option::is_none));
+    assert!((rolling[daily.len()].unwrap() - scalar).abs() < 1e-12);
+
+    // 下方リターンのない窓は None
+    let rising = vec![0.0, 0.01, 0.02, 0.01, 0.03];
+    assert!(YahooFinanceService::calculate_rolling_sortino(&rising, 3, 0.0).iter().all(Option::is_none));
+}
+
+#[test]
+fn influx_export_writes_valid_line_protocol() {
+    let prices: Vec<f64> = (0..30).map(|i| 100.0 + i as f64).collect();
+    let cached = cached_fixture("AAPL", &prices, 15);
+    let path = std::env::temp_dir().join(format!("influx-{}.lp", Uuid::new_v4()));
+    let path_str = path.to_string_lossy().to_string();
+
+    export_influx_line("AAPL".to_string(), (*cached.data).clone(), (*cached.analysis).clone(), path_str).unwrap();
+    let text = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), prices.len());
+    for (i, line) in lines.iter().enumerate() {
+        let parts: Vec<&str> = line.split(' ').collect();
+        assert_eq!(parts.len(), 3, "{}", line);
+        assert_eq!(parts[0], "skew_prices,symbol=AAPL");
+        let fields: Vec<(&str, f64)> = parts[1]
+            .split(',')
+            .map(|f| {
+                let (key, value) = f.split_once('=').unwrap();
+                (key, value.parse::<f64>().unwrap())
+            })
+            .collect();
+        let expected_fields = 2 + usize::from(i >= 4) + usize::from(i >= 19);
+        assert_eq!(fields.len(), expected_fields, "{}", line);
+        assert_eq!(fields[0], ("close", prices[i]));
+        let timestamp: i64 = parts[2].parse().unwrap();
+        assert_eq!(timestamp % 86_400_000_000_000, 0);
+    }
+    // 2023-01-02T00:00:00Z
+    assert!(lines[0].ends_with(" 1672617600000000000"));
+    assert_eq!(YahooFinanceService::escape_influx_tag("BRK B,1=x"), "BRK\\ B\\,1\\=x");
+}
+
+#[test]
+fn asymmetric_beta_detects_higher_down_beta() {
+    let bench: Vec<f64> = standard_normals(51, 400).into_iter().map(|z| 0.01 * z).collect();
+    let noise = standard_normals(52, 400);
+    // 下落日に1.6倍、上昇日に0.7倍反応する銘柄
+    let asset: Vec<f64> = bench.iter().zip(&noise).map(|(b, e)| if *b < 0.0 { 1.6 * b } else { 0.7 * b } + 0.001 * e).collect();
+
+    let beta = YahooFinanceService::calculate_asymmetric_beta(
+        &series_fixture("ASSET", &prices_from_returns(&asset)),
+        &series_fixture("BENCH", &prices_from_returns(&bench)),
+    ).unwrap();
+
+    assert!((beta.up_beta - 0.7).abs() < 0.1, "up {}", beta.up_beta);
+    assert!((beta.down_beta - 1.6).abs() < 0.1, "down {}", beta.down_beta);
+    assert_eq!(beta.up_days + beta.down_days, 400);
+}
+
+#[tokio::test]
+async fn truncated_body_is_retried_until_complete() {
+    let (base_url, hits) = spawn_mock_chart_server(|_, attempt| {
+        let body = chart_json("AAPL", &[10.0, 11.0, 12.0]);
+        if attempt == 0 {
+            (200, body[..body.len() / 2].to_string())
+        } else {
+            (200, body)
+        }
+    }).await;
+    let service = YahooFinanceService::new(Arc::new(SecureCacheManager::new(10, 5))).with_chart_base_url(&base_url);
+
+    let (series, _) = service.get_financial_data("AAPL", "1mo", "1d").await.unwrap();
+    assert_eq!(series.prices, vec![10.0, 11.0, 12.0]);
+    assert_eq!(hits.load(Ordering::SeqCst), 2);
+    assert_eq!(service.telemetry.snapshot().network_errors.get("truncated"), Some(&1));
+
+    // 構文として壊れた（EOFではない）ボディは再試行しない
+    let (base_url, hits) = spawn_mock_chart_server(|_, _| (200, "{\"chart\": ]".to_string())).await;
+    let service = YahooFinanceService::new(Arc::new(SecureCacheManager::new(10, 5))).with_chart_base_url(&base_url);
+    let err = service.get_financial_data("AAPL", "1mo", "1d").await.unwrap_err();
+    assert!(matches!(err, AppError::YahooFinance(_)));
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn rolling_return_distribution_scales_mean_with_horizon() {
+    let daily: Vec<f64> = standard_normals(61, 1000).into_iter().map(|z| 0.0005 + 0.002 * z).collect();
+    let prices = prices_from_returns(&daily);
+    let daily_mean = daily.iter().sum::<f64>() / daily.len() as f64;
+
+    let dist = YahooFinanceService::calculate_rolling_return_distribution(&prices, 20).unwrap();
+    assert!((dist.mean / (20.0 * daily_mean) - 1.0).abs() < 0.05, "mean {} vs {}", dist.mean, 20.0 * daily_mean);
+    assert!(dist.p5 < dist.mean && dist.mean < dist.p95);
+    assert!(dist.std > 0.0);
+    assert!(YahooFinanceService::calculate_rolling_return_distribution(&prices[..15], 20).is_err());
+}
+
+#[test]
+fn metrics_delta_is_zero_when_series_repeats_its_pattern() {
+    // 同じ4日周期のリターンを繰り返すので、周期の区切りで切った指標は一致する
+    let cycle = [0.01, -0.005, 0.002, -0.003];
+    let returns: Vec<f64> = cycle.iter().cycle().take(cycle.len() * 80).copied().collect();
+    let series = series_fixture("CYCLE", &prices_from_returns(&returns));
+    let as_of_a = series.dates[cycle.len() * 40].clone();
+    let as_of_b = series.dates[cycle.len() * 80].clone();
+
+    let delta = YahooFinanceService::calculate_metrics_delta(&series.dates, &series.prices, &as_of_a, &as_of_b).unwrap();
+    for change in [delta.mean_return_daily, delta.std_return_daily, delta.sharpe_annual, delta.skew, delta.max_drawdown, delta.annualized_return] {
+        assert!(change.abs() < 1e-9, "delta {}", change);
+    }
+    assert!(delta.a.sharpe_annual > 0.0);
+    assert!(YahooFinanceService::calculate_metrics_delta(&series.dates, &series.prices, "2020-01-01", &as_of_b).is_err());
+}
+
+#[test]
+fn risk_contributions_split_evenly_for_uncorrelated_equal_weights() {
+    let a: Vec<f64> = standard_normals(71, 2000).into_iter().map(|z| 0.01 * z).collect();
+    let b: Vec<f64> = standard_normals(72, 2000).into_iter().map(|z| 0.01 * z).collect();
+    let series = vec![
+        series_fixture("A", &prices_from_returns(&a)),
+        series_fixture("B", &prices_from_returns(&b)),
+    ];
+    let weights = [0.5, 0.5];
+    let contributions = YahooFinanceService::calculate_risk_contributions(&series, &weights).unwrap();
+
+    // 合計はポートフォリオの年率ボラティリティ
+    let portfolio: Vec<f64> = series[0].prices.windows(2).zip(series[1].prices.windows(2))
+        .map(|(x, y)| 0.5 * (x[1] / x[0] - 1.0) + 0.5 * (y[1] / y[0] - 1.0))
+        .collect();
+    let mean = portfolio.iter().sum::<f64>() / portfolio.len() as f64;
+    let vol = (portfolio.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (portfolio.len() - 1) as f64 * 252.0).sqrt();
+    assert!((contributions.iter().sum::<f64>() - vol).abs() < 1e-9);
+    assert!((contributions[0] / contributions[1] - 1.0).abs() < 0.15, "{:?}", contributions);
+}
+
+#[tokio::test]
+async fn prometheus_export_lists_families_and_escapes_labels() {
+    let cache = SecureCacheManager::new(10, 5);
+    cache.set("AAPL:1y:1d".to_string(), cached_fixture("AAPL", &[10.0, 11.0, 10.5, 12.0], 15)).await.unwrap();
+    cache.set("ODD:1mo:1d".to_string(), cached_fixture("ODD\"\\X", &[5.0, 5.5, 5.2], 15)).await.unwrap();
+    let telemetry = Telemetry::default();
+    telemetry.record_fetch();
+    telemetry.record_cache_miss();
+    telemetry.record_network_error("timeout");
+    telemetry.record_network_error("500");
+
+    let text = YahooFinanceService::render_prometheus(&telemetry.snapshot(), &cache.snapshot_entries().await);
+
+    let mut families = Vec::new();
+    let mut samples = Vec::new();
+    for line in text.lines() {
+        if let Some(rest) = line.strip_prefix("# TYPE ") {
+            let (name, kind) = rest.split_once(' ').unwrap();
+            assert!(kind == "counter" || kind == "gauge");
+            families.push(name.to_string());
+        } else if !line.starts_with('#') {
+            let (series, value) = line.rsplit_once(' ').unwrap();
+            value.parse::<f64>().unwrap();
+            let name = series.split('{').next().unwrap();
+            assert!(families.last().map(String::as_str) == Some(name), "{}", line);
+            samples.push(series.to_string());
+        }
+    }
+    assert_eq!(families, vec![
+        "skew_fetches_total", "skew_cache_hits_total", "skew_cache_misses_total", "skew_network_errors_total",
+        "skew_fetch_latency_avg_ms", "skew_last_price", "skew_sharpe_annual", "skew_std_return_daily",
+    ]);
+    // 単一値4件 + エラーコード2件 + 銘柄別3系列×2銘柄
+    assert_eq!(samples.len(), 4 + 2 + 6);
+    assert!(samples.contains(&"skew_last_price{symbol=\"AAPL\",range=\"1y\",interval=\"1d\"}".to_string()));
+    assert!(samples.contains(&"skew_last_price{symbol=\"ODD\\\"\\\\X\",range=\"1mo\",interval=\"1d\"}".to_string()));
+}
+
+#[test]
+fn half_kelly_curve_is_less_volatile_than_full_kelly() {
+    let returns: Vec<f64> = standard_normals(81, 750).into_iter().map(|z| 0.0005 + 0.02 * z).collect();
+    let kelly = YahooFinanceService::calculate_kelly_fraction(&returns);
+    assert!(kelly > 0.0 && kelly < MAX_KELLY_LEVERAGE);
+
+    let curve_vol = |curve: &[f64]| YahooFinanceService::calculate_simple_returns(curve).iter().map(|r| r * r).sum::<f64>().sqrt();
+    let full = YahooFinanceService::calculate_kelly_equity_curve(&returns, 1.0).unwrap();
+    let half = YahooFinanceService::calculate_kelly_equity_curve(&returns, 0.5).unwrap();
+
+    assert_eq!(full.len(), returns.len() + 1);
+    assert!(curve_vol(&half) < curve_vol(&full));
+    // 日次リターンはレバレッジに比例する
+    assert!(((half[1] - 1.0) / (full[1] - 1.0) - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn dominant_cycle_finds_injected_twenty_day_period() {
+    let noise = standard_normals(91, 750);
+    let returns: Vec<f64> = noise.iter().enumerate()
+        .map(|(t, z)| 0.004 * (2.0 * std::f64::consts::PI * t as f64 / 20.0).sin() + 0.004 * z)
+        .collect();
+    let cycle = YahooFinanceService::detect_dominant_cycle(&returns, 40).unwrap();
+    let period = cycle.expect("cycle should be detected");
+    assert!((18..=22).contains(&period), "period {}", period);
+
+    let white_noise: Vec<f64> = noise.iter().map(|z| 0.01 * z).collect();
+    let none = YahooFinanceService::detect_dominant_cycle(&white_noise, 40).unwrap();
+    assert_eq!(none, None);
+}
+
+#[tokio::test]
+async fn symbol_metadata_aggregates_cached_ranges() {
+    let cache = Arc::new(SecureCacheManager::new(10, 5));
+    let service = YahooFinanceService::new(cache.clone());
+    let short = cached_fixture("AAPL", &[1.0, 2.0, 3.0], 15);
+    let mut long = cached_fixture("AAPL", &[1.0, 2.0, 3.0, 4.0, 5.0], 15);
+    long.cached_at = Utc::now() - Duration::minutes(5);
+    cache.set("AAPL:1mo:1d".to_string(), short.clone()).await.unwrap();
+    cache.set("AAPL:1y:1d".to_string(), long).await.unwrap();
+    cache.set("MSFT:1y:1d".to_string(), cached_fixture("MSFT", &[1.0; 10], 15)).await.unwrap();
+
+    let metadata = service.collect_symbol_metadata("AAPL").await;
+    assert_eq!(metadata.cached_ranges, vec!["1mo", "1y"]);
+    // 長い方の系列の最終日（2023-01-02 から5日分）
+    assert_eq!(metadata.last_data_date.as_deref(), Some("2023-01-06"));
+    assert_eq!(metadata.last_cached_at, Some(short.cached_at.to_rfc3339()));
+
+    let missing = service.collect_symbol_metadata("NFLX").await;
+    assert!(missing.cached_ranges.is_empty() && missing.last_cached_at.is_none());
+}
+
+#[test]
+fn block_bootstrap_is_reproducible_and_tracks_drift() {
+    let returns: Vec<f64> = standard_normals(101, 1000).into_iter().map(|z| 0.0008 + 0.01 * z).collect();
+    let first = YahooFinanceService::simulate_terminal_returns(&returns, 60, 4000, 10, 7).unwrap();
+    let second = YahooFinanceService::simulate_terminal_returns(&returns, 60, 4000, 10, 7).unwrap();
+    assert_eq!(first.p5, second.p5);
+    assert_eq!(first.p95, second.p95);
+    assert!(first.p5 < first.median && first.median < first.p95);
+
+    let drift = returns.iter().sum::<f64>() / returns.len() as f64;
+    assert!((first.mean - drift * 60.0).abs() < 0.02, "mean {} vs {}", first.mean, drift * 60.0);
+    assert!(YahooFinanceService::simulate_terminal_returns(&returns[..5], 60, 100, 10, 7).is_err());
+}
+
+#[test]
+fn drawdown_at_risk_lies_between_median_and_max_drawdown() {
+    // -10%, -25%, -5%, -40% の4つの下落局面
+    let prices = path_through(&[100.0, 90.0, 110.0, 82.5, 120.0, 114.0, 130.0, 78.0, 135.0], 10);
+    let dar = YahooFinanceService::calculate_drawdown_at_risk(&prices, 0.95).unwrap();
+
+    let mut drawdowns = YahooFinanceService::calculate_drawdown_series(&prices);
+    drawdowns.sort_by(|a, b| a.total_cmp(b));
+    let median = YahooFinanceService::calculate_quantile(&drawdowns, 0.5);
+    let max_drawdown = YahooFinanceService::calculate_max_drawdown(&prices);
+
+    assert!((max_drawdown + 0.4).abs() < 1e-12);
+    assert!(max_drawdown <= dar && dar <= median, "max {} dar {} median {}", max_drawdown, dar, median);
+    assert!(dar < -0.2);
+    assert!(YahooFinanceService::calculate_drawdown_at_risk(&prices, 1.0).is_err());
+}
+
+#[test]
+fn standardize_transform_yields_unit_returns() {
+    let daily: Vec<f64> = standard_normals(111, 500).into_iter().map(|z| 0.001 + 0.015 * z).collect();
+    let prices = prices_from_returns(&daily);
+    let service = YahooFinanceService::new(Arc::new(SecureCacheManager::new(10, 5)));
+    let raw = service.analyze_financial_data(&prices).unwrap();
+
+    let result = service.calculate_transformed_analysis(&prices, raw.clone(), "standardize").unwrap();
+    let returns = &result.transformed.returns[1..];
+    let n = returns.len() as f64;
+    let mean = returns.iter().sum::<f64>() / n;
+    let std = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+    assert!(mean.abs() < 1e-12);
+    assert!((std - 1.0).abs() < 1e-12);
+    // 報告される統計量もプレースホルダーを含まない（std は母標準偏差なので √((n-1)/n)）
+    assert!(result.transformed.mean_return_daily.abs() < 1e-12);
+    assert!((result.transformed.std_return_daily - ((n - 1.0) / n).sqrt()).abs() < 1e-12);
+    assert!(result.transformed.sharpe_annual.abs() < 1e-9);
+
+    let demeaned = service.calculate_transformed_analysis(&prices, raw.clone(), "demean").unwrap();
+    assert!(demeaned.transformed.mean_return_daily.abs() < 1e-12);
+    let raw_std = (daily.iter().map(|r| (r - daily.iter().sum::<f64>() / n).powi(2)).sum::<f64>() / n).sqrt();
+    assert!((demeaned.transformed.std_return_daily - raw_std).abs() < 1e-9);
+    // 元の系列もそのまま返す
+    assert_eq!(result.raw.returns, raw.returns);
+    assert_eq!(result.transformed.sma20, raw.sma20);
+    assert!(service.calculate_transformed_analysis(&prices, raw, "log").is_err());
+}
+
+#[test]
+fn deseasonalize_removes_monday_effect() {
+    let mut series = business_day_fixture("MON", 520, 0..0);
+    let noise = standard_normals(121, series.dates.len());
+    let is_monday = |d: &str| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap().weekday() == chrono::Weekday::Mon;
+    let returns: Vec<f64> = series.dates[1..].iter().zip(&noise)
+        .map(|(d, z)| 0.003 * z + if is_monday(d) { 0.01 } else { 0.0 })
+        .collect();
+    series.prices = prices_from_returns(&returns);
+    let service = YahooFinanceService::new(Arc::new(SecureCacheManager::new(10, 5)));
+    let analysis = service.analyze_financial_data(&series.prices).unwrap();
+
+    let adjusted = service.calculate_deseasonalized_analysis(&series, &analysis).unwrap();
+    let monday_gap = |rs: &[f64]| {
+        let (mut mon, mut other) = (Vec::new(), Vec::new());
+        for (d, r) in series.dates[1..].iter().zip(rs) {
+            if is_monday(d) { mon.push(*r) } else { other.push(*r) }
+        }
+        mon.iter().sum::<f64>() / mon.len() as f64 - other.iter().sum::<f64>() / other.len() as f64
+    };
+    assert!(monday_gap(&analysis.returns[1..]) > 0.009);
+    assert!(monday_gap(&adjusted.returns[1..]).abs() < 1e-3, "gap {}", monday_gap(&adjusted.returns[1..]));
+    assert_eq!(adjusted.returns.len(), series.prices.len());
+}
+
+#[tokio::test]
+async fn trend_signal_reports_divergent_horizons() {
+    let (base_url, hits) = spawn_mock_chart_server(|path, _| {
+        let closes: Vec<f64> = if path.contains("range=1mo") {
+            // 直近1か月は下落
+            (0..21).map(|i| 120.0 - i as f64).collect()
+        } else if path.contains("range=6mo") {
+            (0..126).map(|i| 100.0 + (i as f64 * 0.1).sin()).collect()
+        } else {
+            (0..252).map(|i| 60.0 + i as f64 * 0.25).collect()
+        };
+        (200, chart_json("AAPL", &closes))
+    }).await;
+    let service = YahooFinanceService::new(Arc::new(SecureCacheManager::new(10, 5))).with_chart_base_url(&base_url);
+
+    let signal = service.calculate_trend_signal("AAPL", "1d").await.unwrap();
+    assert_eq!(signal.short, "bearish");
+    assert_eq!(signal.long, "bullish");
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+    // 2回目は期間ごとのキャッシュから
+    service.calculate_trend_signal("AAPL", "1d").await.unwrap();
+    assert_eq!(hits.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn sector_exposure_ranks_tracked_etf_first() {
+    let etf_returns: Vec<Vec<f64>> = (0..3)
+        .map(|k| standard_normals(131 + k, 300).into_iter().map(|z| 0.01 * z).collect())
+        .collect();
+    let noise = standard_normals(140, 300);
+    // XLK にほぼ連動する銘柄
+    let stock: Vec<f64> = etf_returns[1].iter().zip(&noise).map(|(r, e)| 1.2 * r + 0.002 * e).collect();
+    let etfs: Vec<(String, SeriesPayload)> = ["XLE", "XLK", "XLF"].iter().zip(&etf_returns)
+        .map(|(name, returns)| (name.to_string(), series_fixture(name, &prices_from_returns(returns))))
+        .collect();
+
+    let exposures = YahooFinanceService::calculate_sector_exposure(&series_fixture("STOCK", &prices_from_returns(&stock)), &etfs).unwrap();
+    assert_eq!(exposures.len(), 3);
+    assert_eq!(exposures[0].0, "XLK");
+    assert!(exposures[0].1 > 0.95);
+    assert!(exposures.windows(2).all(|w| w[0].1 >= w[1].1));
+}
+
+#[derive(Default)]
+struct MemoryStore {
+    values: std::sync::Mutex<HashMap<String, serde_json::Value>>,
+    saves: std::sync::atomic::AtomicUsize,
+}
+
+impl SettingsStore for MemoryStore {
+    fn get(&self, key: &str) -> Option<serde_json::Value> {
+        self.values.lock().unwrap().get(key).cloned()
+    }
+
+    fn set(&self, key: &str, value: serde_json::Value) {
+        self.values.lock().unwrap().insert(key.to_string(), value);
+    }
+
+    async fn save(&self) -> Result<(), String> {
+        self.saves.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn session_replay_reproduces_each_symbol_analysis() {
+    let cache = Arc::new(SecureCacheManager::new(10, 5));
+    let service = YahooFinanceService::new(cache.clone());
+    let aapl = cached_fixture("AAPL", &[10.0, 11.0, 10.5, 12.0], 15);
+    let msft = cached_fixture("MSFT", &[20.0, 19.0, 21.0, 22.0, 23.0], 15);
+    cache.set("AAPL:1y:1d".to_string(), aapl.clone()).await.unwrap();
+    cache.set("MSFT:1y:1d".to_string(), msft.clone()).await.unwrap();
+
+    // save_session / load_session と同じ処理をメモリ上のストアで往復させる
+    let store = MemoryStore::default();
+    let symbols = vec!["AAPL".to_string(), " MSFT ".to_string(), "".to_string()];
+    store_session(&store, " watchlist ", symbols, "1y".to_string(), "1d".to_string(), None).await.unwrap();
+    assert_eq!(store.saves.load(Ordering::SeqCst), 1);
+
+    let result = replay_stored_session(&store, "watchlist", &service).await.unwrap();
+    assert_eq!(result.session.symbols, vec!["AAPL", "MSFT"]);
+    assert_eq!(result.session.options, SessionOptions::default());
+    assert_eq!(result.results.len(), 2);
+    for (entry, expected) in result.results.iter().zip([&aapl, &msft]) {
+        assert!(entry.error.is_none());
+        assert_eq!(entry.series.as_ref().unwrap().prices, expected.data.prices);
+        let analysis = entry.analysis.as_ref().unwrap();
+        assert_eq!(analysis.sharpe_annual, expected.analysis.sharpe_annual);
+        assert_eq!(analysis.returns, expected.analysis.returns);
+    }
+
+    let err = replay_stored_session(&store, "missing", &service).await.unwrap_err();
+    assert!(err.contains("見つかりません"));
+    assert!(replay_stored_session(&MemoryStore::default(), "watchlist", &service).await.is_err());
+    assert!(store_session(&store, "   ", vec!["AAPL".to_string()], "1y".to_string(), "1d".to_string(), None).await.is_err());
+    assert_eq!(store.saves.load(Ordering::SeqCst), 1);
+    assert_eq!(validate_session_name("  my board ").unwrap(), "my board");
+    assert!(validate_session_name(&"x".repeat(65)).is_err());
+}
+
+#[tokio::test]
+async fn session_replay_applies_saved_options() {
+    let cache = Arc::new(SecureCacheManager::new(10, 5));
+    let service = YahooFinanceService::new(cache.clone());
+    let aapl = cached_fixture("AAPL", &[10.0, 11.0, 10.5, 12.0, 11.5], 15);
+    cache.set("AAPL:1y:1d".to_string(), aapl.clone()).await.unwrap();
+
+    let store = MemoryStore::default();
+    let options = Some(serde_json::json!({ "transform": "demean" }));
+    store_session(&store, "demeaned", vec!["AAPL".to_string()], "1y".to_string(), "1d".to_string(), options).await.unwrap();
+
+    let result = replay_stored_session(&store, "demeaned", &service).await.unwrap();
+    assert_eq!(result.session.options.transform.as_deref(), Some("demean"));
+    let analysis = result.results[0].analysis.as_ref().unwrap();
+    let expected = service.calculate_transformed_analysis(&aapl.data.prices, (*aapl.analysis).clone(), "demean").unwrap();
+    assert_eq!(analysis.returns, expected.transformed.returns);
+    assert!(analysis.mean_return_daily.abs() < 1e-12);
+
+    // 未対応のオプションや変換は保存時に弾き、ストアには書き込まない
+    let session = |options: serde_json::Value| build_session("x", vec!["AAPL".to_string()], "1y".to_string(), "1d".to_string(), Some(options));
+    assert!(session(serde_json::json!({ "show_sma": true })).is_err());
+    assert!(session(serde_json::json!({ "transform": "log" })).unwrap_err().contains("未対応の変換"));
+    assert!(store_session(&store, "x", vec!["AAPL".to_string()], "1y".to_string(), "1d".to_string(), Some(serde_json::json!({ "transform": "log" }))).await.is_err());
+    assert!(replay_stored_session(&store, "x", &service).await.is_err());
+}
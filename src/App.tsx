@@ -16,6 +16,8 @@ type SeriesPayload = {
 type AnalysisResult = {
   mean_return_daily: number; std_return_daily: number; sharpe_annual: number;
   sma5: (number|null)[]; sma20: (number|null)[]; returns: number[];
+  extra?: Record<string, number | (number|null)[]>;
+  disabled?: string[];
 };
 
 type UserSettings = {
@@ -24,6 +26,9 @@ type UserSettings = {
   default_interval: string;
   cache_ttl_minutes: number;
   theme: string;
+  negative_cache_minutes?: number;
+  cache_events_enabled?: boolean;
+  enabled_indicators?: string[] | null;
 };
 
 type CacheStats = {
@@ -33,6 +38,11 @@ type CacheStats = {
   session_id: string;
 };
 
+// 無効化された指標は値ではなく「無効」と表示する
+function formatMetric(ana: AnalysisResult, name: "mean_return_daily" | "std_return_daily" | "sharpe_annual", digits: number) {
+  return ana.disabled?.includes(name) ? "無効" : ana[name].toFixed(digits);
+}
+
 export default function App() {
   const [symbol, setSymbol] = useState("7203.T");
   const [range, setRange] = useState("1y");
@@ -296,8 +306,8 @@ export default function App() {
         {series && ana && (
           <>
             <div className="stats-container">
-              <strong>{series.symbol}</strong> ・ 日次平均 {ana.mean_return_daily.toFixed(6)} ・
-              日次σ {ana.std_return_daily.toFixed(6)} ・ 年率Sharpe {ana.sharpe_annual.toFixed(3)}
+              <strong>{series.symbol}</strong> ・ 日次平均 {formatMetric(ana, "mean_return_daily", 6)} ・
+              日次σ {formatMetric(ana, "std_return_daily", 6)} ・ 年率Sharpe {formatMetric(ana, "sharpe_annual", 3)}
             </div>
             <div className="chart-container">
               <ResponsiveContainer>